@@ -116,6 +116,15 @@ impl<'a> ConsumePrefix<&'static str> for FileMapStream<'a>
   }
 }
 
+impl<'a> ConsumeWhile for FileMapStream<'a>
+{
+  fn consume_while<P>(&mut self, pred: P) -> usize where
+   P: FnMut(char) -> bool
+  {
+    self.str_stream.consume_while(pred)
+  }
+}
+
 impl<'a> HasNext for FileMapStream<'a>
 {
   fn has_next(&self) -> bool {
@@ -134,6 +143,17 @@ impl<'a> StreamSpan for Range<FileMapStream<'a>>
   }
 }
 
+impl<'a> StreamSlice for Range<FileMapStream<'a>>
+{
+  type Output = &'a str;
+  fn stream_slice(&self) -> Self::Output {
+    Range {
+      start: self.start.str_stream.clone(),
+      end: self.end.str_stream.clone()
+    }.stream_slice()
+  }
+}
+
 #[cfg(test)]
 mod test {
   extern crate syntex_syntax;
@@ -157,4 +177,14 @@ mod test {
     assert!(stream.next() == None);
     assert!(stream2.next() == None);
   }
+
+  #[test]
+  fn test_filemap_slice() {
+    let codemap = CodeMap::new();
+    let filemap = codemap.new_filemap(format!("fake"), None, format!("let x"));
+    let start = filemap.stream();
+    let mut end = start.clone();
+    assert!(end.consume_prefix("let"));
+    assert_eq!(Range { start: start, end: end }.stream_slice(), "let");
+  }
 }
@@ -0,0 +1,160 @@
+// Copyright 2017 Pierre Talbot (IRCAM)
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Random generation of inputs used by the functions `generate_<rule>` of the grammars annotated with `#![generator]`.
+//!
+//! The generator is a xorshift pseudo-random number generator, the same seed always produces the same inputs. The generated inputs can be written in the `run-pass` and `run-fail` directories of a test data directory with `write_run_cases`.
+
+use std::fs::{File, create_dir_all};
+use std::io;
+use std::io::Write;
+use std::path::Path;
+
+pub struct Generator
+{
+  state: u64,
+  /// Depth of rule calls after which the generator stops choosing at random and takes the shortest way out of the recursion.
+  pub max_depth: usize,
+  /// Upper bound of the number of iterations of `e*` and `e+`.
+  pub max_repeat: usize,
+  /// Number of inputs generated before giving up when the generated inputs are rejected by the grammar, for example because of a syntactic predicate.
+  pub max_attempts: usize
+}
+
+impl Generator
+{
+  pub fn new(seed: u64) -> Generator {
+    Generator {
+      // The state of xorshift must not be zero.
+      state: if seed == 0 { 0x9E3779B97F4A7C15 } else { seed },
+      max_depth: 8,
+      max_repeat: 4,
+      max_attempts: 100
+    }
+  }
+
+  pub fn next_u64(&mut self) -> u64 {
+    self.state ^= self.state << 13;
+    self.state ^= self.state >> 7;
+    self.state ^= self.state << 17;
+    self.state
+  }
+
+  /// Number in the interval `[0, n[`.
+  pub fn below(&mut self, n: usize) -> usize {
+    if n == 0 { 0 }
+    else { (self.next_u64() % (n as u64)) as usize }
+  }
+
+  pub fn gen_bool(&mut self) -> bool {
+    self.below(2) == 0
+  }
+
+  pub fn repeat(&mut self) -> usize {
+    self.below(self.max_repeat + 1)
+  }
+
+  /// Character in one of the inclusive intervals `(lo, hi)`.
+  pub fn char_in(&mut self, intervals: &[(char, char)]) -> char {
+    let (lo, hi) = intervals[self.below(intervals.len())];
+    let (lo, hi) = (lo as u32, hi as u32);
+    let c = lo + (self.next_u64() % ((hi - lo + 1) as u64)) as u32;
+    ::std::char::from_u32(c).unwrap_or(intervals[0].0)
+  }
+
+  /// Printable ASCII character generated for `.`.
+  pub fn any_char(&mut self) -> char {
+    self.char_in(&[(' ', '~')])
+  }
+
+  /// Inserts, removes or replaces one character of `input` to produce an input close to a valid one.
+  pub fn mutate(&mut self, input: &str) -> String {
+    let mut chars: Vec<char> = input.chars().collect();
+    let pos = self.below(chars.len() + 1);
+    match self.below(3) {
+      0 if pos < chars.len() => { chars.remove(pos); }
+      1 if pos < chars.len() => { chars[pos] = self.any_char(); }
+      _ => {
+        let c = self.any_char();
+        chars.insert(pos, c);
+      }
+    }
+    chars.into_iter().collect()
+  }
+}
+
+/// Writes each valid input in `<dir>/run-pass/<prefix>-<i>` and each invalid input in `<dir>/run-fail/<prefix>-<i>`, which is the layout of the test data directories.
+pub fn write_run_cases(dir: &Path, prefix: &str, valid: &[String], invalid: &[String]) -> io::Result<()> {
+  write_cases(&dir.join("run-pass"), prefix, valid)?;
+  write_cases(&dir.join("run-fail"), prefix, invalid)
+}
+
+fn write_cases(dir: &Path, prefix: &str, inputs: &[String]) -> io::Result<()> {
+  create_dir_all(dir)?;
+  for (i, input) in inputs.iter().enumerate() {
+    let mut file = File::create(dir.join(format!("{}-{}", prefix, i)))?;
+    file.write_all(input.as_bytes())?;
+  }
+  Ok(())
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use std::env;
+  use std::fs::{read_dir, remove_dir_all};
+
+  #[test]
+  fn test_seed_reproducibility() {
+    let mut g1 = Generator::new(42);
+    let mut g2 = Generator::new(42);
+    let mut g3 = Generator::new(43);
+    let s1: Vec<u64> = (0..10).map(|_| g1.next_u64()).collect();
+    let s2: Vec<u64> = (0..10).map(|_| g2.next_u64()).collect();
+    let s3: Vec<u64> = (0..10).map(|_| g3.next_u64()).collect();
+    assert_eq!(s1, s2);
+    assert!(s1 != s3);
+  }
+
+  #[test]
+  fn test_char_in() {
+    let mut g = Generator::new(0);
+    for _ in 0..100 {
+      let c = g.char_in(&[('a', 'c'), ('x', 'x')]);
+      assert!((c >= 'a' && c <= 'c') || c == 'x');
+      assert!(g.below(5) < 5);
+    }
+  }
+
+  #[test]
+  fn test_mutate() {
+    let mut g = Generator::new(7);
+    for _ in 0..100 {
+      let mutated = g.mutate("abc");
+      let len = mutated.chars().count();
+      assert!(len >= 2 && len <= 4);
+    }
+    assert_eq!(g.mutate("").chars().count(), 1);
+  }
+
+  #[test]
+  fn test_write_run_cases() {
+    let dir = env::temp_dir().join("oak_test_write_run_cases");
+    let _ = remove_dir_all(&dir);
+    write_run_cases(&dir, "generated", &[format!("a"), format!("b")], &[format!("c")]).unwrap();
+    assert_eq!(read_dir(dir.join("run-pass")).unwrap().count(), 2);
+    assert_eq!(read_dir(dir.join("run-fail")).unwrap().count(), 1);
+    remove_dir_all(&dir).unwrap();
+  }
+}
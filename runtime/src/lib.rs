@@ -27,6 +27,7 @@ pub mod str_stream;
 pub mod parse_state;
 pub mod stream;
 pub mod file_map_stream;
+pub mod generator;
 
 pub fn make_span(lo: usize, hi: usize) -> Span {
   mk_sp(
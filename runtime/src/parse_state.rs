@@ -19,6 +19,7 @@ use self::ParseResult::*;
 use std::collections::hash_set::HashSet;
 use std::cmp::Ord;
 use std::fmt::{Formatter, Debug, Error};
+use std::borrow::Cow;
 
 pub trait IntoState<S, T>
 {
@@ -36,13 +37,13 @@ impl<S, T, R> IntoState<S, T> for R where
 
 pub struct ParseExpectation<S>
 {
-  expected: HashSet<&'static str>,
+  expected: HashSet<Cow<'static, str>>,
   farthest_read: S
 }
 
 impl<S> ParseExpectation<S>
 {
-  pub fn new(farthest_read: S, expected: Vec<&'static str>) -> ParseExpectation<S> {
+  pub fn new(farthest_read: S, expected: Vec<Cow<'static, str>>) -> ParseExpectation<S> {
     ParseExpectation {
       expected: expected.into_iter().collect(),
       farthest_read: farthest_read
@@ -118,8 +119,8 @@ pub struct ParseState<S, T>
 {
   /// The farthest read into the stream at which we encountered an error.
   pub farthest_read: S,
-  /// Expected items at position `farthest_read`. Duplicate entries are possible.
-  pub expected: Vec<&'static str>,
+  /// Expected items at position `farthest_read`. Duplicate entries are possible. Messages of failed semantic predicates and actions are dynamically built.
+  pub expected: Vec<Cow<'static, str>>,
   pub failed: bool,
   /// The current stream that can be partially or fully consumed.
   pub current: S,
@@ -149,15 +150,37 @@ impl<S, T> ParseState<S, T> where
     !self.is_failed()
   }
 
+  /// Expected items at position `farthest_read` as string slices, as they were before the messages of semantic predicates and actions were dynamically built.
+  pub fn expected_items(&self) -> Vec<&str> {
+    self.expected.iter().map(|expect| expect.as_ref()).collect()
+  }
+
   #[inline]
-  pub fn error(&mut self, expect: &'static str) {
+  pub fn error<E>(&mut self, expect: E) where
+   E: Into<Cow<'static, str>>
+  {
     self.failed = true;
     if self.current > self.farthest_read {
       self.farthest_read = self.current.clone();
-      self.expected = vec![expect];
+      self.expected = vec![expect.into()];
     }
     else if self.current == self.farthest_read {
-      self.expected.push(expect);
+      self.expected.push(expect.into());
+    }
+  }
+
+  /// Saves the current position and the number of expectations already recorded at this position. It is used with `label_expectations` to replace the expectations of a rule by a label.
+  pub fn expectations_checkpoint(&self) -> (S, usize) {
+    let len = if self.farthest_read == self.current { self.expected.len() } else { 0 };
+    (self.current.clone(), len)
+  }
+
+  /// If the state failed without reading input beyond the position saved in `checkpoint`, the expectations recorded since `checkpoint` are replaced by `label`.
+  pub fn label_expectations(&mut self, checkpoint: (S, usize), label: &'static str) {
+    let (position, len) = checkpoint;
+    if self.failed && self.farthest_read == position {
+      self.expected.truncate(len);
+      self.expected.push(Cow::Borrowed(label));
     }
   }
 
@@ -266,3 +289,49 @@ impl<S, T, P> ConsumePrefix<P> for ParseState<S, T> where
     self.current.consume_prefix(prefix)
   }
 }
+
+impl<S, T> ConsumeWhile for ParseState<S, T> where
+  S: ConsumeWhile
+{
+  fn consume_while<P>(&mut self, pred: P) -> usize where
+   P: FnMut(char) -> bool
+  {
+    self.current.consume_while(pred)
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use str_stream::*;
+
+  #[test]
+  fn test_label_expectations() {
+    let mut state: ParseState<StrStream, ()> = "ab".into_state();
+    let checkpoint = state.expectations_checkpoint();
+    state.error("a");
+    state.error("b");
+    state.label_expectations(checkpoint, "letter");
+    assert_eq!(state.expected, vec![Cow::Borrowed("letter")]);
+  }
+
+  #[test]
+  fn test_label_expectations_after_progress() {
+    let mut state: ParseState<StrStream, ()> = "ab".into_state();
+    state.error("c");
+    let checkpoint = state.expectations_checkpoint();
+    state.next();
+    state.error("b");
+    state.label_expectations(checkpoint, "letter");
+    assert_eq!(state.expected, vec![Cow::Borrowed("b")]);
+  }
+
+  #[test]
+  fn test_dynamic_expectation() {
+    let mut state: ParseState<StrStream, ()> = "ab".into_state();
+    state.error("a");
+    state.error(format!("number"));
+    assert_eq!(state.expected, vec![Cow::Borrowed("a"), Cow::Owned(format!("number"))]);
+    assert_eq!(state.expected_items(), vec!["a", "number"]);
+  }
+}
@@ -162,6 +162,35 @@ impl<'a> ConsumePrefix<&'static str> for StrStream<'a>
   }
 }
 
+impl<'a> ConsumeWhile for StrStream<'a>
+{
+  fn consume_while<P>(&mut self, mut pred: P) -> usize where
+   P: FnMut(char) -> bool
+  {
+    let bytes = self.raw_data.as_bytes();
+    let mut offset = self.bytes_offset;
+    let mut consumed = 0;
+    while offset < bytes.len() {
+      // ASCII characters are read directly from the bytes, other characters are decoded.
+      let (c, len) =
+        if bytes[offset] < 0x80 {
+          (bytes[offset] as char, 1)
+        }
+        else {
+          let c = self.raw_data[offset..].chars().next().unwrap();
+          (c, c.len_utf8())
+        };
+      if !pred(c) {
+        break;
+      }
+      offset += len;
+      consumed += 1;
+    }
+    self.bytes_offset = offset;
+    consumed
+  }
+}
+
 impl<'a> HasNext for StrStream<'a>
 {
   fn has_next(&self) -> bool {
@@ -179,6 +208,15 @@ impl<'a> StreamSpan for Range<StrStream<'a>>
   }
 }
 
+impl<'a> StreamSlice for Range<StrStream<'a>>
+{
+  type Output = &'a str;
+  fn stream_slice(&self) -> Self::Output {
+    self.start.assert_same_raw_data(&self.end);
+    &self.start.raw_data[self.start.bytes_offset..self.end.bytes_offset]
+  }
+}
+
 #[cfg(test)]
 mod test {
   use super::*;
@@ -201,6 +239,20 @@ mod test {
     consume_prefix_test(s1, "z", false, Some('a'));
   }
 
+  #[test]
+  fn test_consume_while() {
+    let mut s = "ab1éè2 c".stream();
+    assert_eq!(s.consume_while(|c| c.is_alphabetic()), 2);
+    assert_eq!(s.bytes_offset, 2);
+    assert_eq!(s.consume_while(|c| c.is_alphabetic()), 0);
+    assert_eq!(s.consume_while(|c| c != ' '), 4);
+    assert_eq!(s.bytes_offset, 8);
+    assert_eq!(s.next(), Some(' '));
+    assert_eq!(s.consume_while(|_| true), 1);
+    assert_eq!(s.consume_while(|_| true), 0);
+    assert_eq!(s.next(), None);
+  }
+
   fn test_str_stream<'a, I>(mut s1: StrStream<'a>, chars: I) where
    I: Iterator<Item=char>
   {
@@ -231,6 +283,16 @@ mod test {
     test_str_stream(abc.stream(), abc.chars());
   }
 
+  #[test]
+  fn test_stream_slice() {
+    let start = "aé b".stream();
+    let mut end = start.clone();
+    end.next();
+    end.next();
+    assert_eq!(Range { start: start.clone(), end: end.clone() }.stream_slice(), "aé");
+    assert_eq!(Range { start: end.clone(), end: end }.stream_slice(), "");
+  }
+
   #[test]
   fn test_empty_stream() {
     let mut empty = "".stream();
@@ -54,6 +54,13 @@ pub trait ConsumePrefix<P>
   fn consume_prefix(&mut self, prefix: P) -> bool;
 }
 
+/// Consumes the characters from the current position in the stream as long as `pred` holds, and returns the number of characters consumed. The stream is positioned on the first character not satisfying `pred`. It is implemented by the streams of the runtime and used by the generated code to scan repetitions of character classes, it is not required from custom streams.
+pub trait ConsumeWhile
+{
+  fn consume_while<P>(&mut self, pred: P) -> usize where
+   P: FnMut(char) -> bool;
+}
+
 /// Returns `true` if an item can be read from the stream with `Iterator::next`.
 pub trait HasNext
 {
@@ -65,3 +72,10 @@ pub trait StreamSpan
   type Output;
   fn stream_span(&self) -> Self::Output;
 }
+
+/// Retrieves the data of the stream between two positions without copying it. For example, it is a `&'a str` for a stream of type `StrStream<'a>`.
+pub trait StreamSlice
+{
+  type Output;
+  fn stream_slice(&self) -> Self::Output;
+}
@@ -17,6 +17,7 @@
 pub use identifier::*;
 
 use rust;
+use rust::AstBuilder;
 use std::fmt::{Formatter, Display, Error};
 
 pub type RTy = rust::P<rust::Ty>;
@@ -32,7 +33,8 @@ pub use partial::Partial;
 pub use middle::typing::ast::IType;
 pub use middle::typing::ast::Type;
 
-use middle::analysis::ast::GrammarAttributes;
+use middle::analysis::ast::{GrammarAttributes, RuleAttributes};
+use middle::first_set::FirstSet;
 
 use std::collections::HashMap;
 use std::default::Default;
@@ -53,7 +55,9 @@ pub struct Grammar<'a, 'b:'a, ExprInfo>
   pub stream_alias: RItem,
   pub rust_functions: HashMap<Ident, RItem>,
   pub rust_items: Vec<RItem>,
-  pub attributes: GrammarAttributes
+  pub attributes: GrammarAttributes,
+  /// FIRST sets of the expressions, computed after typing (see `middle::first_set`).
+  pub first_sets: HashMap<usize, FirstSet>
 }
 
 impl<'a, 'b, ExprInfo> Grammar<'a, 'b, ExprInfo>
@@ -70,7 +74,8 @@ impl<'a, 'b, ExprInfo> Grammar<'a, 'b, ExprInfo>
       stream_alias: quote_item!(cx, pub type Stream<'a> = StrStream<'a>;).unwrap(),
       rust_functions: HashMap::new(),
       rust_items: vec![],
-      attributes: GrammarAttributes::default()
+      attributes: GrammarAttributes::default(),
+      first_sets: HashMap::new()
     }
   }
 
@@ -106,6 +111,25 @@ impl<'a, 'b, ExprInfo> Grammar<'a, 'b, ExprInfo>
       .span_note_without_error(span, msg.as_str());
   }
 
+  pub fn rule_attributes(&self, rule: Ident) -> RuleAttributes {
+    self.attributes.rules.get(&rule)
+      .cloned()
+      .unwrap_or_default()
+  }
+
+  pub fn has_entry_rules(&self) -> bool {
+    self.attributes.rules.values().any(|attrs| attrs.entry)
+  }
+
+  /// Every rule is public if no entry rule is declared.
+  pub fn is_public_rule(&self, rule: Ident) -> bool {
+    !self.has_entry_rules() || self.rule_attributes(rule).entry
+  }
+
+  pub fn is_reachable_rule(&self, rule: Ident) -> bool {
+    !self.rule_attributes(rule).unreachable
+  }
+
   pub fn find_rule_by_ident(&self, id: Ident) -> Rule {
     self.rules.iter()
       .find(|r| r.ident() == id)
@@ -117,6 +141,24 @@ impl<'a, 'b, ExprInfo> Grammar<'a, 'b, ExprInfo>
     self.find_rule_by_ident(id).expr_idx
   }
 
+  /// The streams of the runtime (`StrStream` and `FileMapStream`) implement `ConsumeWhile`, custom streams are not required to.
+  pub fn is_runtime_stream(&self) -> bool {
+    match &self.stream_alias.node {
+      &rust::ItemKind::Ty(ref ty, _) => {
+        match &ty.node {
+          &rust::TyKind::Path(None, ref path) => {
+            path.segments.last().map_or(false, |segment| {
+              let name = segment.identifier.name.as_str();
+              &*name == "StrStream" || &*name == "FileMapStream"
+            })
+          }
+          _ => false
+        }
+      }
+      _ => unreachable!()
+    }
+  }
+
   pub fn stream_generics(&self) -> rust::Generics {
     match &self.stream_alias.node {
       &rust::ItemKind::Ty(_, ref generics) => generics.clone(),
@@ -155,6 +197,87 @@ impl<'a, 'b, ExprInfo> Grammar<'a, 'b, ExprInfo>
     let stream_ty = self.stream_type();
     quote_ty!(self.cx, <Range<$stream_ty> as StreamSpan>::Output)
   }
+
+  pub fn slice_type(&self) -> RTy {
+    let stream_ty = self.stream_type();
+    quote_ty!(self.cx, <Range<$stream_ty> as StreamSlice>::Output)
+  }
+
+  /// Return type of the Rust code called by `action`, `None` if it cannot be found.
+  pub fn action_return_type(&self, action: &RustAction) -> Option<rust::FunctionRetTy> {
+    match action {
+      &RustAction::Function(ident) => {
+        self.rust_functions.get(&ident).and_then(|item|
+          match &item.node {
+            &rust::ItemKind::Fn(ref decl,..) => Some(decl.output.clone()),
+            _ => None
+          })
+      }
+      &RustAction::Path(ref path) => {
+        self.path_action_type(path).map(|ty| rust::FunctionRetTy::Ty(ty))
+      }
+      &RustAction::Closure(ref closure) => {
+        match &closure.node {
+          &rust::ExprKind::Closure(_, ref decl, _, _) => Some(decl.output.clone()),
+          _ => None
+        }
+      }
+      &RustAction::Constructor(_, ty) => {
+        Some(rust::FunctionRetTy::Ty(quote_ty!(self.cx, $ty)))
+      }
+    }
+  }
+
+  /// An action is fallible if it returns a `Result<T, E>`, in which case `Err` makes the expression fail.
+  pub fn is_fallible_action(&self, action: &RustAction) -> bool {
+    self.action_return_type(action)
+      .map_or(false, |ret_ty| result_ok_type(&ret_ty).is_some())
+  }
+
+  /// Type of the value built by a path action `Enum::Variant`. The enumeration must be declared in the grammar and cannot be generic.
+  pub fn path_action_type(&self, path: &rust::Path) -> Option<RTy> {
+    let len = path.segments.len();
+    if len < 2 {
+      return None;
+    }
+    let enum_name = path.segments[len - 2].identifier;
+    let variant = path.segments[len - 1].identifier;
+    let is_variant = self.rust_items.iter().any(|item|
+      match &item.node {
+        &rust::ItemKind::Enum(ref def, ref generics) => {
+          item.ident == enum_name
+          && generics.lifetimes.is_empty()
+          && generics.ty_params.is_empty()
+          && def.variants.iter().any(|v| v.node.name == variant)
+        }
+        _ => false
+      });
+    if is_variant {
+      let mut enum_path = path.clone();
+      enum_path.segments.pop();
+      Some(self.cx.ty_path(enum_path))
+    }
+    else {
+      None
+    }
+  }
+}
+
+/// Type `T` of the return type `Result<T, E>` (the last segment of the path must be `Result`).
+pub fn result_ok_type(ret_ty: &rust::FunctionRetTy) -> Option<RTy> {
+  if let &rust::FunctionRetTy::Ty(ref ty) = ret_ty {
+    if let rust::TyKind::Path(None, ref path) = ty.node {
+      let last = path.segments.last();
+      if let Some(&rust::PathSegment { identifier, parameters: Some(ref params), .. }) = last {
+        if let &rust::PathParameters::AngleBracketed(ref data) = &**params {
+          if identifier.name == "Result" && !data.types.is_empty() {
+            return Some(data.types[0].clone());
+          }
+        }
+      }
+    }
+  }
+  None
 }
 
 impl<'a, 'b, ExprInfo> Index<usize> for Grammar<'a, 'b, ExprInfo>
@@ -233,9 +356,33 @@ pub enum Expression
   ZeroOrOne(usize), // expr?
   NotPredicate(usize), // !expr
   AndPredicate(usize), // &expr
-  SemanticAction(usize, Ident), // expr > function
+  SemanticAction(usize, RustAction), // expr > function
+  SemanticPredicate(usize, RExpr), // expr &{ |x| predicate }
   TypeAscription(usize, IType), // expr -> () or expr -> (^)
   SpannedExpr(usize), // .. expr
+  Cut, // ^
+}
+
+/// Rust code called on the values produced by the expression of a semantic action.
+#[derive(Clone, Debug)]
+pub enum RustAction
+{
+  Function(Ident), // expr > function
+  Path(rust::Path), // expr > Enum::Variant
+  Closure(RExpr), // expr > |x: T| -> R { ... }
+  Constructor(Ident, Ident) // expr > constructor, generated with the type it builds by `#![generate_ast]`
+}
+
+impl Display for RustAction
+{
+  fn fmt(&self, formatter: &mut Formatter) -> Result<(), Error> {
+    match self {
+      &RustAction::Function(ref ident) => formatter.write_fmt(format_args!("{}", ident)),
+      &RustAction::Path(ref path) => formatter.write_str(rust::path_to_string(path).as_str()),
+      &RustAction::Closure(ref expr) => formatter.write_str(rust::expr_to_string(expr).as_str()),
+      &RustAction::Constructor(ref constructor, _) => formatter.write_fmt(format_args!("{}", constructor))
+    }
+  }
 }
 
 #[derive(Clone, Debug)]
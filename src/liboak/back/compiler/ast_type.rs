@@ -0,0 +1,294 @@
+// Copyright 2017 Pierre Talbot (IRCAM)
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Generates the types and the constructors of the rules annotated by `middle::typing::ast_generation`.
+//!
+//! The fields of a type are the values of the expression wrapped by its constructor. A field calling a rule on a recursive path of the generated types (found by `typing::surface`) is boxed, otherwise the type would have an infinite size.
+
+use middle::typing::ast::*;
+use middle::analysis::ast::AstType;
+use back::compiler::rtype::*;
+use rust;
+use rust::AstBuilder;
+use std::collections::HashSet;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Boxing
+{
+  /// `T`
+  Unboxed,
+  /// `Box<T>`
+  Boxed,
+  /// `Option<Box<T>>`
+  OptionalBoxed
+}
+
+pub struct AstField
+{
+  pub name: Ident,
+  /// Expression producing the value of the field.
+  pub expr_idx: usize,
+  pub boxing: Boxing,
+  /// Type of the constructor parameter, it is the type of the value produced by the parser.
+  param_ty: RTy,
+  field_ty: RTy,
+  value: RExpr
+}
+
+/// Generated type of a rule with its fields.
+pub enum AstShape
+{
+  Struct {
+    name: Ident,
+    constructor: Ident,
+    fields: Vec<AstField>
+  },
+  Enum {
+    name: Ident,
+    /// Name, constructor and fields of each variant.
+    variants: Vec<(Ident, Ident, Vec<AstField>)>
+  }
+}
+
+pub struct AstTypeCompiler<'a: 'c, 'b: 'a, 'c>
+{
+  grammar: &'c TGrammar<'a, 'b>
+}
+
+impl<'a, 'b, 'c> AstTypeCompiler<'a, 'b, 'c>
+{
+  pub fn new(grammar: &'c TGrammar<'a, 'b>) -> Self {
+    AstTypeCompiler {
+      grammar: grammar
+    }
+  }
+
+  pub fn compile(grammar: &'c TGrammar<'a, 'b>) -> Vec<RItem> {
+    let compiler = AstTypeCompiler::new(grammar);
+    grammar.rules.iter()
+      .filter(|rule| grammar.is_reachable_rule(rule.ident()))
+      .flat_map(|rule| compiler.compile_rule(*rule).into_iter())
+      .collect()
+  }
+
+  pub fn shape(&self, rule: Rule) -> Option<AstShape> {
+    self.grammar.rule_attributes(rule.ident()).ast_type.map(|ast_type| match ast_type {
+      AstType::Struct { name, constructor } => AstShape::Struct {
+        name: name,
+        constructor: constructor,
+        fields: self.struct_fields(rule.ident(), self.constructor_arg(rule.expr_idx))
+      },
+      AstType::Enum { name, variants } => {
+        let branches = match self.grammar.expr_by_index(rule.expr_idx) {
+          Choice(branches) => branches,
+          _ => unreachable!("Enum types are only generated for choice rules.")
+        };
+        AstShape::Enum {
+          name: name,
+          variants: branches.into_iter().zip(variants.into_iter())
+            .map(|(branch, (variant, constructor))|
+              (variant, constructor, self.variant_fields(rule.ident(), self.constructor_arg(branch))))
+            .collect()
+        }
+      }
+    })
+  }
+
+  /// The rule with a generated type called by `expr_idx`, if any.
+  pub fn generated_rule(&self, expr_idx: usize) -> Option<Ident> {
+    match self.grammar.expr_by_index(self.value_expr(expr_idx)) {
+      NonTerminalSymbol(rule) if self.grammar.rule_attributes(rule).ast_type.is_some() => Some(rule),
+      _ => None
+    }
+  }
+
+  fn compile_rule(&self, rule: Rule) -> Vec<RItem> {
+    match self.shape(rule) {
+      None => vec![],
+      Some(AstShape::Struct { name, constructor, fields }) => {
+        vec![
+          self.struct_item(name, &fields),
+          self.constructor(constructor, name, &fields,
+            |cx, span, fields| cx.expr_struct_ident(span, name, fields.iter()
+              .map(|field| cx.field_imm(span, field.name, field.value.clone()))
+              .collect()))
+        ]
+      }
+      Some(AstShape::Enum { name, variants }) => {
+        let mut items = vec![];
+        let mut enum_variants = vec![];
+        for (variant, constructor, fields) in variants {
+          enum_variants.push(self.cx().variant(self.span(), variant,
+            fields.iter().map(|field| field.field_ty.clone()).collect()));
+          items.push(self.constructor(constructor, name, &fields,
+            |cx, span, fields| {
+              let path = cx.expr_path(cx.path(span, vec![name, variant]));
+              if fields.is_empty() { path }
+              else {
+                cx.expr_call(span, path, fields.iter().map(|field| field.value.clone()).collect())
+              }
+            }));
+        }
+        items.insert(0, self.public_type(self.cx().item_enum(self.span(), name,
+          rust::EnumDef { variants: enum_variants })));
+        items
+      }
+    }
+  }
+
+  /// `expr_idx` is the semantic action `e > constructor` built during the typing, we retrieve `e`.
+  fn constructor_arg(&self, expr_idx: usize) -> usize {
+    match self.grammar.expr_by_index(expr_idx) {
+      SemanticAction(child, _) => child,
+      _ => unreachable!("The expression of a generated type must be wrapped in its constructor.")
+    }
+  }
+
+  fn field_exprs(&self, expr_idx: usize) -> Vec<usize> {
+    match self.grammar[expr_idx].ty.clone() {
+      Type::Unit => vec![],
+      Type::Tuple(indexes) => indexes,
+      _ => vec![self.value_expr(expr_idx)]
+    }
+  }
+
+  /// In a sequence such as `"(" e ")"`, the value is produced by `e`.
+  pub fn value_expr(&self, expr_idx: usize) -> usize {
+    match self.grammar.expr_by_index(expr_idx) {
+      Sequence(seq) => {
+        let values: Vec<_> = seq.into_iter()
+          .filter(|idx| self.grammar[*idx].ty != Type::Unit)
+          .collect();
+        if values.len() == 1 { self.value_expr(values[0]) }
+        else { expr_idx }
+      }
+      _ => expr_idx
+    }
+  }
+
+  /// Fields are named after the rule they call, `field_i` otherwise.
+  fn struct_fields(&self, rule: Ident, expr_idx: usize) -> Vec<AstField> {
+    let mut names = HashSet::new();
+    self.field_exprs(expr_idx).into_iter().enumerate()
+      .map(|(i, field_idx)| {
+        let name = match self.called_rule(field_idx) {
+          Some(callee) => ident_to_string(callee),
+          None => format!("field_{}", i)
+        };
+        let mut unique_name = name.clone();
+        let mut suffix = 1;
+        while !names.insert(unique_name.clone()) {
+          suffix += 1;
+          unique_name = format!("{}_{}", name, suffix);
+        }
+        self.field(rule, field_idx, string_to_ident(self.cx(), unique_name))
+      })
+      .collect()
+  }
+
+  fn variant_fields(&self, rule: Ident, expr_idx: usize) -> Vec<AstField> {
+    self.field_exprs(expr_idx).into_iter().enumerate()
+      .map(|(i, field_idx)| {
+        let name = string_to_ident(self.cx(), format!("field_{}", i));
+        self.field(rule, field_idx, name)
+      })
+      .collect()
+  }
+
+  /// The rule called by `r`, `r*`, `r+` or `r?`.
+  fn called_rule(&self, expr_idx: usize) -> Option<Ident> {
+    match self.grammar.expr_by_index(expr_idx) {
+      NonTerminalSymbol(rule) => Some(rule),
+        ZeroOrMore(child)
+      | OneOrMore(child)
+      | ZeroOrOne(child) => {
+        match self.grammar.expr_by_index(self.value_expr(child)) {
+          NonTerminalSymbol(rule) => Some(rule),
+          _ => None
+        }
+      }
+      _ => None
+    }
+  }
+
+  fn field(&self, rule: Ident, expr_idx: usize, name: Ident) -> AstField {
+    let cx = self.cx();
+    let param_ty = TypeCompiler::compile(self.grammar, expr_idx);
+    let (boxing, field_ty, value) = match self.grammar.expr_by_index(expr_idx) {
+      NonTerminalSymbol(callee) if self.is_recursive_call(rule, callee) =>
+        (Boxing::Boxed, quote_ty!(cx, Box<$param_ty>), quote_expr!(cx, Box::new($name))),
+      ZeroOrOne(child) if self.called_rule(expr_idx).map_or(false, |callee| self.is_recursive_call(rule, callee)) => {
+        let child_ty = TypeCompiler::compile(self.grammar, self.value_expr(child));
+        (Boxing::OptionalBoxed, quote_ty!(cx, Option<Box<$child_ty>>), quote_expr!(cx, $name.map(Box::new)))
+      }
+      _ => (Boxing::Unboxed, param_ty.clone(), quote_expr!(cx, $name))
+    };
+    AstField {
+      name: name,
+      expr_idx: expr_idx,
+      boxing: boxing,
+      param_ty: param_ty,
+      field_ty: field_ty,
+      value: value
+    }
+  }
+
+  fn is_recursive_call(&self, rule: Ident, callee: Ident) -> bool {
+    self.grammar.rule_attributes(rule).boxed_calls.contains(&callee)
+  }
+
+  fn struct_item(&self, name: Ident, fields: &Vec<AstField>) -> RItem {
+    let fields = fields.iter()
+      .map(|field| rust::StructField {
+        span: self.span(),
+        ident: Some(field.name),
+        vis: rust::Visibility::Public,
+        id: rust::DUMMY_NODE_ID,
+        ty: field.field_ty.clone(),
+        attrs: vec![]
+      })
+      .collect();
+    self.public_type(self.cx().item_struct(self.span(), name,
+      rust::VariantData::Struct(fields, rust::DUMMY_NODE_ID)))
+  }
+
+  fn public_type(&self, item: RItem) -> RItem {
+    let derive = quote_attr!(self.cx(), #[derive(Clone, Debug, PartialEq)]);
+    item.map(|mut item| {
+      item.vis = rust::Visibility::Public;
+      item.attrs.push(derive);
+      item
+    })
+  }
+
+  fn constructor<F>(&self, constructor: Ident, ty: Ident, fields: &Vec<AstField>, make_value: F) -> RItem where
+   F: FnOnce(&ExtCtxt, Span, &Vec<AstField>) -> RExpr
+  {
+    let cx = self.cx();
+    let span = self.span();
+    let params = fields.iter()
+      .map(|field| cx.arg(span, field.name, field.param_ty.clone()))
+      .collect();
+    let body = cx.block_expr(make_value(cx, span, fields));
+    cx.item_fn(span, constructor, params, cx.ty_ident(span, ty), body)
+  }
+
+  fn span(&self) -> Span {
+    self.cx().call_site()
+  }
+
+  fn cx(&self) -> &'a ExtCtxt<'b> {
+    self.grammar.cx
+  }
+}
@@ -0,0 +1,304 @@
+// Copyright 2017 Pierre Talbot (IRCAM)
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Generates the traits `Visitor` and `Fold` for the types generated with `#![generate_ast]`.
+//!
+//! Both traits have one method per generated type, `visit_<rule>` and `fold_<rule>`. Their default implementations call the functions `walk_<rule>` and `fold_<rule>` which visit (or fold) the fields of the type. Fields containing generated types inside vectors, options or tuples are traversed, the other fields are left untouched.
+
+use middle::typing::ast::*;
+use middle::typing::ast::Type::*;
+use back::compiler::ast_type::*;
+use rust;
+use rust::AstBuilder;
+
+pub struct AstVisitorCompiler<'a: 'c, 'b: 'a, 'c>
+{
+  grammar: &'c TGrammar<'a, 'b>,
+  ast: AstTypeCompiler<'a, 'b, 'c>
+}
+
+impl<'a, 'b, 'c> AstVisitorCompiler<'a, 'b, 'c>
+{
+  pub fn compile(grammar: &'c TGrammar<'a, 'b>) -> Vec<RItem> {
+    let compiler = AstVisitorCompiler {
+      grammar: grammar,
+      ast: AstTypeCompiler::new(grammar)
+    };
+    let shapes: Vec<_> = grammar.rules.iter()
+      .filter(|rule| grammar.is_reachable_rule(rule.ident()))
+      .filter_map(|rule| compiler.ast.shape(*rule).map(|shape| (rule.ident(), shape)))
+      .collect();
+    if shapes.is_empty() {
+      vec![]
+    }
+    else {
+      compiler.compile_traits(shapes)
+    }
+  }
+
+  fn compile_traits(&self, shapes: Vec<(Ident, AstShape)>) -> Vec<RItem> {
+    let cx = self.cx();
+    let mut visit_methods = vec![];
+    let mut fold_methods = vec![];
+    let mut items = vec![];
+    for (rule, shape) in shapes {
+      let ty = match &shape {
+        &AstShape::Struct { name, .. } => name,
+        &AstShape::Enum { name, .. } => name
+      };
+      let visit = self.visit_name(rule);
+      let walk = self.walk_name(rule);
+      let fold = self.fold_name(rule);
+      visit_methods.push(quote_tokens!(cx,
+        fn $visit(&mut self, node: &$ty) {
+          $walk(self, node)
+        }
+      ));
+      fold_methods.push(quote_tokens!(cx,
+        fn $fold(&mut self, node: $ty) -> $ty {
+          $fold(self, node)
+        }
+      ));
+      let walk_body = self.walk_shape(&shape);
+      items.push(quote_item!(cx,
+        #[allow(unused_variables)]
+        pub fn $walk<V: Visitor + ?Sized>(visitor: &mut V, node: &$ty) {
+          $walk_body
+        }
+      ).expect("Quotation of a walk function."));
+      let fold_body = self.fold_shape(&shape);
+      items.push(quote_item!(cx,
+        #[allow(unused_variables)]
+        pub fn $fold<F: Fold + ?Sized>(folder: &mut F, node: $ty) -> $ty {
+          $fold_body
+        }
+      ).expect("Quotation of a fold function."));
+    }
+    items.push(quote_item!(cx,
+      pub trait Visitor {
+        $visit_methods
+      }
+    ).expect("Quotation of the visitor trait."));
+    items.push(quote_item!(cx,
+      pub trait Fold {
+        $fold_methods
+      }
+    ).expect("Quotation of the fold trait."));
+    items
+  }
+
+  fn walk_shape(&self, shape: &AstShape) -> RExpr {
+    let cx = self.cx();
+    match shape {
+      &AstShape::Struct { ref fields, .. } => {
+        let stmts: Vec<_> = fields.iter()
+          .filter_map(|field| {
+            let name = field.name;
+            self.walk_value(field.expr_idx, quote_expr!(cx, &node.$name))
+          })
+          .map(|walk| cx.stmt_semi(walk))
+          .collect();
+        cx.expr_block(cx.block(self.span(), stmts))
+      }
+      &AstShape::Enum { name, ref variants } => {
+        let arms: Vec<_> = variants.iter()
+          .map(|&(variant, _, ref fields)| {
+            let pats = fields.iter()
+              .map(|field| cx.pat_ident_binding_mode(self.span(), field.name,
+                rust::BindingMode::ByRef(rust::Mutability::Immutable)))
+              .collect();
+            let stmts: Vec<_> = fields.iter()
+              .filter_map(|field| {
+                let name = field.name;
+                self.walk_value(field.expr_idx, quote_expr!(cx, $name))
+              })
+              .map(|walk| cx.stmt_semi(walk))
+              .collect();
+            cx.arm(self.span(), vec![self.variant_pat(name, variant, pats)],
+              cx.expr_block(cx.block(self.span(), stmts)))
+          })
+          .collect();
+        cx.expr_match(self.span(), quote_expr!(cx, *node), arms)
+      }
+    }
+  }
+
+  /// `value` is a reference to the value produced by `expr_idx`.
+  fn walk_value(&self, expr_idx: usize, value: RExpr) -> Option<RExpr> {
+    let cx = self.cx();
+    if let Some(rule) = self.ast.generated_rule(expr_idx) {
+      let visit = self.visit_name(rule);
+      return Some(quote_expr!(cx, visitor.$visit($value)));
+    }
+    let expr_idx = self.ast.value_expr(expr_idx);
+    match self.grammar[expr_idx].ty.clone() {
+      List(child) => self.walk_value(child, quote_expr!(cx, item))
+        .map(|walk| quote_expr!(cx, for item in $value.iter() { $walk; })),
+      Optional(child) => self.walk_value(child, quote_expr!(cx, item))
+        .map(|walk| quote_expr!(cx, if let Some(ref item) = *$value { $walk; })),
+      Tuple(indexes) => {
+        let names = self.tuple_names(indexes.len());
+        let walks: Vec<_> = indexes.into_iter().zip(names.iter())
+          .filter_map(|(idx, name)| self.walk_value(idx, quote_expr!(cx, $name)))
+          .map(|walk| cx.stmt_semi(walk))
+          .collect();
+        if walks.is_empty() {
+          None
+        }
+        else {
+          let pat = cx.pat_tuple(self.span(), names.into_iter()
+            .map(|name| cx.pat_ident_binding_mode(self.span(), name,
+              rust::BindingMode::ByRef(rust::Mutability::Immutable)))
+            .collect());
+          let mut stmts = vec![quote_stmt!(cx, let $pat = *$value;)
+            .expect("Quotation of a tuple pattern.")];
+          stmts.extend(walks.into_iter());
+          Some(cx.expr_block(cx.block(self.span(), stmts)))
+        }
+      }
+      _ => None
+    }
+  }
+
+  fn fold_shape(&self, shape: &AstShape) -> RExpr {
+    let cx = self.cx();
+    match shape {
+      &AstShape::Struct { name, ref fields, .. } => {
+        let fields = fields.iter()
+          .map(|field| {
+            let field_name = field.name;
+            cx.field_imm(self.span(), field_name,
+              self.fold_field(field, quote_expr!(cx, node.$field_name)))
+          })
+          .collect();
+        cx.expr_struct_ident(self.span(), name, fields)
+      }
+      &AstShape::Enum { name, ref variants } => {
+        let arms: Vec<_> = variants.iter()
+          .map(|&(variant, _, ref fields)| {
+            let pats = fields.iter()
+              .map(|field| cx.pat_ident(self.span(), field.name))
+              .collect();
+            let values: Vec<_> = fields.iter()
+              .map(|field| {
+                let name = field.name;
+                self.fold_field(field, quote_expr!(cx, $name))
+              })
+              .collect();
+            let path = cx.expr_path(cx.path(self.span(), vec![name, variant]));
+            let value = if values.is_empty() { path } else { cx.expr_call(self.span(), path, values) };
+            cx.arm(self.span(), vec![self.variant_pat(name, variant, pats)], value)
+          })
+          .collect();
+        cx.expr_match(self.span(), quote_expr!(cx, node), arms)
+      }
+    }
+  }
+
+  fn fold_field(&self, field: &AstField, value: RExpr) -> RExpr {
+    let cx = self.cx();
+    match field.boxing {
+      Boxing::Unboxed => self.fold_value(field.expr_idx, value.clone()).unwrap_or(value),
+      Boxing::Boxed => {
+        let fold = self.fold_value(field.expr_idx, quote_expr!(cx, *$value))
+          .expect("A boxed field contains a generated type.");
+        quote_expr!(cx, Box::new($fold))
+      }
+      Boxing::OptionalBoxed => {
+        let child = match self.grammar.expr_by_index(field.expr_idx) {
+          ZeroOrOne(child) => child,
+          _ => unreachable!("An optional boxed field is produced by `e?`.")
+        };
+        let fold = self.fold_value(child, quote_expr!(cx, *item))
+          .expect("A boxed field contains a generated type.");
+        quote_expr!(cx, $value.map(|item| Box::new($fold)))
+      }
+    }
+  }
+
+  /// `value` is the value produced by `expr_idx`, `None` if it does not contain a generated type.
+  fn fold_value(&self, expr_idx: usize, value: RExpr) -> Option<RExpr> {
+    let cx = self.cx();
+    if let Some(rule) = self.ast.generated_rule(expr_idx) {
+      let fold = self.fold_name(rule);
+      return Some(quote_expr!(cx, folder.$fold($value)));
+    }
+    let expr_idx = self.ast.value_expr(expr_idx);
+    match self.grammar[expr_idx].ty.clone() {
+      List(child) => self.fold_value(child, quote_expr!(cx, item))
+        .map(|fold| quote_expr!(cx, $value.into_iter().map(|item| $fold).collect())),
+      Optional(child) => self.fold_value(child, quote_expr!(cx, item))
+        .map(|fold| quote_expr!(cx, $value.map(|item| $fold))),
+      Tuple(indexes) => {
+        let names = self.tuple_names(indexes.len());
+        let folds: Vec<_> = indexes.into_iter().zip(names.iter())
+          .map(|(idx, name)| self.fold_value(idx, quote_expr!(cx, $name)))
+          .collect();
+        if folds.iter().all(|fold| fold.is_none()) {
+          None
+        }
+        else {
+          let values = folds.into_iter().zip(names.iter())
+            .map(|(fold, name)| fold.unwrap_or(quote_expr!(cx, $name)))
+            .collect();
+          let pat = cx.pat_tuple(self.span(), names.iter()
+            .map(|name| cx.pat_ident(self.span(), *name))
+            .collect());
+          let tuple = cx.expr_tuple(self.span(), values);
+          Some(quote_expr!(cx, {
+            let $pat = $value;
+            $tuple
+          }))
+        }
+      }
+      _ => None
+    }
+  }
+
+  fn variant_pat(&self, ty: Ident, variant: Ident, pats: Vec<RPat>) -> RPat {
+    let path = self.cx().path(self.span(), vec![ty, variant]);
+    if pats.is_empty() {
+      self.cx().pat_path(self.span(), path)
+    }
+    else {
+      self.cx().pat_tuple_struct(self.span(), path, pats)
+    }
+  }
+
+  fn tuple_names(&self, len: usize) -> Vec<Ident> {
+    (0..len)
+      .map(|i| string_to_ident(self.cx(), format!("tuple_{}", i)))
+      .collect()
+  }
+
+  fn visit_name(&self, rule: Ident) -> Ident {
+    string_to_ident(self.cx(), format!("visit_{}", rule))
+  }
+
+  fn walk_name(&self, rule: Ident) -> Ident {
+    string_to_ident(self.cx(), format!("walk_{}", rule))
+  }
+
+  fn fold_name(&self, rule: Ident) -> Ident {
+    string_to_ident(self.cx(), format!("fold_{}", rule))
+  }
+
+  fn span(&self) -> Span {
+    self.cx().call_site()
+  }
+
+  fn cx(&self) -> &'a ExtCtxt<'b> {
+    self.grammar.cx
+  }
+}
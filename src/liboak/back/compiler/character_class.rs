@@ -46,24 +46,65 @@ impl CharacterClassCompiler
     }
   }
 
-  fn compile_interval(&self, cx: &ExtCtxt,
-    char_interval: CharacterInterval, x: Ident) -> RExpr
-  {
-    let CharacterInterval{lo, hi} = char_interval;
-    quote_expr!(cx, ($x >= $lo && $x <= $hi))
+  fn compile_condition(&self, cx: &ExtCtxt, x: Ident) -> RExpr {
+    class_condition(cx, &self.classes.intervals, x)
   }
+}
 
-  fn compile_condition(&self, cx: &ExtCtxt, x: Ident) -> RExpr {
-    let mut intervals = self.classes.intervals.iter().cloned();
-    let first_interval = intervals.next()
-      .expect("Empty character intervals should be forbidden at the parsing stage.");
-    intervals
-      .map(|char_interval| self.compile_interval(cx, char_interval, x))
-      .fold(
-        self.compile_interval(cx, first_interval, x),
-        |accu, interval| quote_expr!(cx, $accu || $interval)
-      )
+/// Compiles a test checking if the character `x` belongs to one of the `intervals`. When several intervals contain ASCII characters, the ASCII characters are looked up in a 128-bit bitmap split in two `u64` and the intervals are only compared against characters beyond the ASCII range.
+pub fn class_condition(cx: &ExtCtxt, intervals: &[CharacterInterval], x: Ident) -> RExpr {
+  let ascii_intervals = intervals.iter()
+    .filter(|interval| interval.lo < ASCII_END)
+    .count();
+  if ascii_intervals > 1 {
+    bitmap_condition(cx, intervals, x)
+  }
+  else {
+    intervals_condition(cx, intervals.iter().cloned(), x)
+      .expect("Empty character intervals should be forbidden at the parsing stage.")
+  }
+}
+
+const ASCII_END: char = '\u{80}';
+
+fn bitmap_condition(cx: &ExtCtxt, intervals: &[CharacterInterval], x: Ident) -> RExpr {
+  let mut masks = [0u64, 0u64];
+  let mut non_ascii = vec![];
+  for &CharacterInterval{lo, hi} in intervals {
+    if lo < ASCII_END {
+      let hi_ascii = if hi < ASCII_END { hi as u32 } else { 0x7f };
+      for code in (lo as u32)..(hi_ascii + 1) {
+        masks[(code / 64) as usize] |= 1u64 << (code % 64);
+      }
+    }
+    if hi >= ASCII_END {
+      let lo = if lo < ASCII_END { ASCII_END } else { lo };
+      non_ascii.push(CharacterInterval::new(lo, hi));
+    }
   }
+  let low_mask = masks[0];
+  let high_mask = masks[1];
+  let fallback = intervals_condition(cx, non_ascii.into_iter(), x)
+    .unwrap_or(quote_expr!(cx, false));
+  quote_expr!(cx, {
+    let code = $x as u32;
+    if code < 64 { ($low_mask >> code) & 1 == 1 }
+    else if code < 128 { ($high_mask >> (code - 64)) & 1 == 1 }
+    else { $fallback }
+  })
+}
+
+fn intervals_condition<I>(cx: &ExtCtxt, intervals: I, x: Ident) -> Option<RExpr> where
+ I: Iterator<Item=CharacterInterval>
+{
+  intervals
+    .map(|CharacterInterval{lo, hi}|
+      if lo == hi { quote_expr!(cx, $x == $lo) }
+      else { quote_expr!(cx, ($x >= $lo && $x <= $hi)) })
+    .fold(None, |accu, interval| match accu {
+      None => Some(interval),
+      Some(accu) => Some(quote_expr!(cx, $accu || $interval))
+    })
 }
 
 impl CompileExpr for CharacterClassCompiler
@@ -13,6 +13,8 @@
 // limitations under the License.
 
 use back::compiler::*;
+use back::compiler::character_class::class_condition;
+use middle::first_set::FirstSet;
 
 pub struct ChoiceCompiler
 {
@@ -47,35 +49,59 @@ impl CompileExpr for ChoiceCompiler
     continuation = context.success_as_closure(continuation);
 
     let mark = context.next_mark_name();
+    let first_char = context.next_first_char_name();
     let branch_failed = context.next_branch_failed_name();
     context.push_mut_ref_fv(branch_failed, quote_ty!(cx, bool));
 
+    // A cut variable is only declared if one branch (but the last one) contains a cut.
+    let mut choices = self.choices.clone();
+    let last = choices.pop().unwrap();
+    let cut = if choices.iter().any(|&idx| self.branch_has_cut(context.grammar(), idx)) {
+      let cut = context.next_cut_name();
+      context.push_mut_ref_fv(cut, quote_ty!(cx, ::std::cell::Cell<bool>));
+      Some(cut)
+    } else {
+      None
+    };
+
     // Each branch of the choice must be compiled in the same variable names environment (they share names of the variables they are building) and with a fresh success continuation size (each branch might create independent success continuation).
     let scope = context.save_scope();
 
-    let mut choices = self.choices.clone();
-    let last = choices.pop().unwrap();
+    context.push_cut_var(cut);
     let mut branches: Vec<_> = choices.into_iter()
       .map(|idx| {
         context.restore_scope(scope.clone());
-        continuation.compile_and_wrap(context, self.compiler, idx,
-          quote_stmt!(cx, $branch_failed = false;))
+        let branch = continuation.compile_and_wrap(context, self.compiler, idx,
+          quote_stmt!(cx, $branch_failed = false;));
+        self.guard_branch(context, idx, first_char, branch, continuation.failure())
       })
       .collect();
+    context.pop_cut_var();
     // The last branch does not need to assign `false` to the variable `branch_failed`.
     context.restore_scope(scope.clone());
+    if cut.is_some() {
+      context.pop_mut_ref_fv();
+    }
     context.pop_mut_ref_fv();
     let (success, failure) = continuation.unwrap();
-    branches.push(context.compile(self.compiler, last, success, failure));
+    context.push_cut_var(None);
+    let last_branch = context.compile(self.compiler, last, success, failure.clone());
+    branches.push(self.guard_branch(context, last, first_char, last_branch, failure));
+    context.pop_cut_var();
 
     let mut branches_iter = branches.into_iter();
     let first = branches_iter.next().unwrap();
 
+    // A failed branch that went through a cut makes the whole choice fail.
+    let backtrack = match cut {
+      Some(cut) => quote_expr!(cx, $branch_failed && !$cut.get()),
+      None => quote_expr!(cx, $branch_failed)
+    };
     let choice = branches_iter
       .rev()
       .fold(quote_expr!(cx, state), |accu, branch|
         quote_expr!(cx,
-          if $branch_failed {
+          if $backtrack {
             let mut state = state.restore_from_failure($mark.clone());
             let state = $branch;
             $accu
@@ -83,11 +109,76 @@ impl CompileExpr for ChoiceCompiler
           else { state }
         ));
 
+    let cut_decl = cut.and_then(|cut| quote_stmt!(cx, let $cut = ::std::cell::Cell::new(false);));
+    let first_char_decl =
+      if self.choices.iter().any(|&idx| self.dispatch_first_set(context.grammar(), idx).is_some()) {
+        quote_stmt!(cx, let $first_char = $mark.clone().next();)
+      }
+      else { None };
     quote_expr!(cx, {
       let $mark = state.mark();
+      $first_char_decl
       let mut $branch_failed = true;
+      $cut_decl
       let state = $first;
       $choice
     })
   }
 }
+
+impl ChoiceCompiler
+{
+  /// The FIRST set of a branch is used if it does not contain every character.
+  fn dispatch_first_set<'a>(&self, grammar: &'a TGrammar, branch: usize) -> Option<&'a FirstSet> {
+    match grammar.first_sets.get(&branch) {
+      Some(first) if !first.is_full() => Some(first),
+      _ => None
+    }
+  }
+
+  /// The next character is read once at the beginning of the choice. A branch whose FIRST set does not contain it is not tried, instead we record the expectations it would have reported and we continue with its failure continuation.
+  fn guard_branch<'a, 'b, 'c>(&self, context: &mut Context<'a, 'b, 'c>, branch: usize,
+    first_char: Ident, code: RExpr, failure: RExpr) -> RExpr
+  {
+    let cx = context.cx();
+    let first = match self.dispatch_first_set(context.grammar(), branch) {
+      Some(first) => first.clone(),
+      None => return code
+    };
+    let x = cx.ident_of("x");
+    let condition = class_condition(cx, &first.chars, x);
+    let mut skip_branch: Vec<_> = first.expectations.iter()
+      .map(|expectation| {
+        let expectation = expectation.as_str();
+        quote_stmt!(cx, state.error($expectation);).unwrap()
+      })
+      .collect();
+    skip_branch.push(cx.stmt_expr(failure));
+    let skip_branch = cx.expr_block(cx.block(context.expr_span(branch), skip_branch));
+    quote_expr!(cx,
+      if $first_char.map_or(false, |$x| $condition) {
+        $code
+      }
+      else {
+        $skip_branch
+      }
+    )
+  }
+
+  /// Cuts are elements of a sequence directly inside a branch (see `middle::analysis::cut`).
+  fn branch_has_cut(&self, grammar: &TGrammar, branch: usize) -> bool {
+    match grammar.expr_by_index(branch) {
+        SemanticAction(child, _)
+      | SemanticPredicate(child, _)
+      | TypeAscription(child, _)
+      | SpannedExpr(child) => self.branch_has_cut(grammar, child),
+      Sequence(seq) => seq.into_iter().any(|idx|
+        match grammar.expr_by_index(idx) {
+          Cut => true,
+          _ => false
+        }),
+      Cut => true,
+      _ => false
+    }
+  }
+}
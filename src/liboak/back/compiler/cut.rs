@@ -0,0 +1,49 @@
+// Copyright 2017 Pierre Talbot (IRCAM)
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use back::compiler::*;
+
+pub struct CutCompiler;
+
+impl CutCompiler
+{
+  pub fn recognizer() -> CutCompiler {
+    CutCompiler
+  }
+
+  pub fn parser() -> CutCompiler {
+    CutCompiler
+  }
+}
+
+impl CompileExpr for CutCompiler
+{
+  fn compile_expr<'a, 'b, 'c>(&self, context: &mut Context<'a, 'b, 'c>,
+    continuation: Continuation) -> RExpr
+  {
+    match context.current_cut_var() {
+      // The cut variable is a `Cell` so it can be set from the success closures where it is passed by reference.
+      Some(cut) => {
+        continuation
+          .map_success(|success, _| quote_expr!(context.cx(), {
+            $cut.set(true);
+            $success
+          }))
+          .unwrap_success()
+      }
+      // A cut in the last branch of a choice has no alternative to discard.
+      None => continuation.unwrap_success()
+    }
+  }
+}
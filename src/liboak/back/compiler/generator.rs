@@ -0,0 +1,232 @@
+// Copyright 2017 Pierre Talbot (IRCAM)
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Generates the random input generators of a grammar annotated with `#![generator]`.
+//!
+//! For each rule, `generate_<rule>(gen)` walks the expression of the rule and chooses at random the branch of the choices and the number of iterations of the repetitions. When the depth of rule calls exceeds `gen.max_depth`, the generator stops the repetitions and takes the branch that leaves the recursion the fastest. Syntactic and semantic predicates are not taken into account during the generation, instead the inputs rejected by the recognizer of the rule are discarded and generated again. `generate_invalid_<rule>(gen)` mutates a valid input until the recognizer rejects it.
+//!
+//! The inputs are read with `&str` streams, so the generators can only be used with the default stream type.
+
+use middle::typing::ast::*;
+use back::name_factory::*;
+use rust;
+use rust::AstBuilder;
+use std::collections::HashMap;
+use std::usize;
+
+pub struct GeneratorCompiler<'a: 'c, 'b: 'a, 'c>
+{
+  grammar: &'c TGrammar<'a, 'b>,
+  /// Minimal depth of rule calls needed to terminate the generation of a rule.
+  rules_depth: HashMap<Ident, usize>
+}
+
+impl<'a, 'b, 'c> GeneratorCompiler<'a, 'b, 'c>
+{
+  pub fn compile(grammar: &'c TGrammar<'a, 'b>) -> Vec<RItem> {
+    if !grammar.attributes.generator {
+      return vec![];
+    }
+    let mut compiler = GeneratorCompiler {
+      grammar: grammar,
+      rules_depth: HashMap::new()
+    };
+    compiler.compute_rules_depth();
+    grammar.rules.iter()
+      .filter(|rule| grammar.is_reachable_rule(rule.ident()))
+      .flat_map(|rule| compiler.compile_rule(*rule).into_iter())
+      .collect()
+  }
+
+  fn compute_rules_depth(&mut self) {
+    let mut changed = true;
+    while changed {
+      changed = false;
+      for rule in &self.grammar.rules {
+        let depth = self.expr_depth(rule.expr_idx);
+        if depth < self.rule_depth(rule.ident()) {
+          self.rules_depth.insert(rule.ident(), depth);
+          changed = true;
+        }
+      }
+    }
+  }
+
+  fn rule_depth(&self, rule: Ident) -> usize {
+    self.rules_depth.get(&rule).cloned().unwrap_or(usize::MAX)
+  }
+
+  fn expr_depth(&self, expr_idx: usize) -> usize {
+    match self.grammar.expr_by_index(expr_idx) {
+      NonTerminalSymbol(rule) => self.rule_depth(rule).saturating_add(1),
+      Sequence(children) => children.into_iter()
+        .map(|child| self.expr_depth(child))
+        .max().unwrap_or(0),
+      Choice(branches) => branches.into_iter()
+        .map(|branch| self.expr_depth(branch))
+        .min().unwrap_or(0),
+      OneOrMore(child)
+    | SemanticAction(child, _)
+    | SemanticPredicate(child, _)
+    | TypeAscription(child, _)
+    | SpannedExpr(child) => self.expr_depth(child),
+      _ => 0
+    }
+  }
+
+  fn compile_rule(&self, rule: Rule) -> Vec<RItem> {
+    let cx = self.cx();
+    let rule_name = rule.ident();
+    let generate_into = self.generate_into_name(rule_name);
+    let generate = string_to_ident(cx, format!("generate_{}", rule_name));
+    let generate_invalid = string_to_ident(cx, format!("generate_invalid_{}", rule_name));
+    let accept = string_to_ident(cx, format!("accept_{}", rule_name));
+    let recognize = recognizer_name(cx, rule_name);
+    let body = self.compile_expr(rule.expr_idx);
+    vec![
+      quote_item!(cx,
+        #[allow(unused_variables)]
+        fn $generate_into(gen: &mut oak_runtime::generator::Generator, out: &mut String, depth: usize) {
+          $body;
+        }
+      ).expect("Quotation of a generation function."),
+      quote_item!(cx,
+        #[allow(dead_code)]
+        fn $accept(input: &str) -> bool {
+          match $recognize(oak_runtime::IntoState::into_state(input)).into_result() {
+            oak_runtime::ParseResult::Success(_) => true,
+            _ => false
+          }
+        }
+      ).expect("Quotation of an acceptance function."),
+      quote_item!(cx,
+        #[allow(dead_code)]
+        pub fn $generate(gen: &mut oak_runtime::generator::Generator) -> Option<String> {
+          for _ in 0..gen.max_attempts {
+            let mut out = String::new();
+            $generate_into(gen, &mut out, 0);
+            if $accept(out.as_str()) {
+              return Some(out);
+            }
+          }
+          None
+        }
+      ).expect("Quotation of a generator."),
+      quote_item!(cx,
+        #[allow(dead_code)]
+        pub fn $generate_invalid(gen: &mut oak_runtime::generator::Generator) -> Option<String> {
+          for _ in 0..gen.max_attempts {
+            if let Some(valid) = $generate(gen) {
+              let mutated = gen.mutate(valid.as_str());
+              if !$accept(mutated.as_str()) {
+                return Some(mutated);
+              }
+            }
+          }
+          None
+        }
+      ).expect("Quotation of an invalid input generator.")
+    ]
+  }
+
+  /// Generates the text of `expr_idx` into `out`.
+  fn compile_expr(&self, expr_idx: usize) -> RExpr {
+    let cx = self.cx();
+    match self.grammar.expr_by_index(expr_idx) {
+      StrLiteral(lit) => {
+        let lit = lit.as_str();
+        quote_expr!(cx, out.push_str($lit))
+      }
+      AnySingleChar => quote_expr!(cx, out.push(gen.any_char())),
+      CharacterClass(class) => {
+        let intervals: Vec<_> = class.intervals.iter()
+          .map(|interval| {
+            let (lo, hi) = (interval.lo, interval.hi);
+            quote_expr!(cx, ($lo, $hi))
+          })
+          .collect();
+        let intervals = cx.expr_vec_slice(self.span(), intervals);
+        quote_expr!(cx, out.push(gen.char_in($intervals)))
+      }
+      Sequence(children) => {
+        let stmts = children.into_iter()
+          .map(|child| cx.stmt_semi(self.compile_expr(child)))
+          .collect();
+        cx.expr_block(cx.block(self.span(), stmts))
+      }
+      Choice(branches) => self.compile_choice(branches),
+      ZeroOrMore(child) => {
+        let child = self.compile_expr(child);
+        quote_expr!(cx,
+          if depth < gen.max_depth {
+            for _ in 0..gen.repeat() { $child; }
+          })
+      }
+      OneOrMore(child) => {
+        let child = self.compile_expr(child);
+        quote_expr!(cx, {
+          $child;
+          if depth < gen.max_depth {
+            for _ in 0..gen.repeat() { $child; }
+          }
+        })
+      }
+      ZeroOrOne(child) => {
+        let child = self.compile_expr(child);
+        quote_expr!(cx, if depth < gen.max_depth && gen.gen_bool() { $child; })
+      }
+      NonTerminalSymbol(rule) => {
+        let generate_into = self.generate_into_name(rule);
+        quote_expr!(cx, $generate_into(gen, out, depth + 1))
+      }
+      SemanticAction(child, _)
+    | SemanticPredicate(child, _)
+    | TypeAscription(child, _)
+    | SpannedExpr(child) => self.compile_expr(child),
+      NotPredicate(_)
+    | AndPredicate(_)
+    | Cut => quote_expr!(cx, ())
+    }
+  }
+
+  fn compile_choice(&self, branches: Vec<usize>) -> RExpr {
+    let cx = self.cx();
+    let shortest = branches.iter().cloned()
+      .min_by_key(|branch| self.expr_depth(*branch))
+      .expect("A choice has at least one branch.");
+    let shortest = self.compile_expr(shortest);
+    let num_branches = branches.len();
+    let last = branches.len() - 1;
+    let mut arms: Vec<_> = branches[..last].iter().enumerate()
+      .map(|(i, branch)| cx.arm(self.span(), vec![quote_pat!(cx, $i)], self.compile_expr(*branch)))
+      .collect();
+    arms.push(cx.arm(self.span(), vec![quote_pat!(cx, _)], self.compile_expr(branches[last])));
+    let random_branch = cx.expr_match(self.span(), quote_expr!(cx, gen.below($num_branches)), arms);
+    quote_expr!(cx,
+      if depth >= gen.max_depth { $shortest }
+      else { $random_branch })
+  }
+
+  fn generate_into_name(&self, rule: Ident) -> Ident {
+    string_to_ident(self.cx(), format!("generate_into_{}", rule))
+  }
+
+  fn span(&self) -> Span {
+    self.cx().call_site()
+  }
+
+  fn cx(&self) -> &'a ExtCtxt<'b> {
+    self.grammar.cx
+  }
+}
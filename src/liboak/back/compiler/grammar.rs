@@ -15,6 +15,10 @@
 use middle::typing::ast::*;
 use back::code_printer::*;
 use back::compiler::rule::*;
+use back::compiler::ast_type::*;
+use back::compiler::ast_visitor::*;
+use back::compiler::printer::*;
+use back::compiler::generator::*;
 use rust;
 
 pub struct GrammarCompiler<'a, 'b: 'a>
@@ -88,11 +92,16 @@ impl<'a, 'b> GrammarCompiler<'a, 'b>
     let mut mod_content = self.compile_rules();
     mod_content.extend(self.grammar.rust_items.clone().into_iter());
     mod_content.extend(self.grammar.rust_functions.values().cloned());
+    mod_content.extend(AstTypeCompiler::compile(&self.grammar).into_iter());
+    mod_content.extend(AstVisitorCompiler::compile(&self.grammar).into_iter());
+    mod_content.extend(PrinterCompiler::compile(&self.grammar).into_iter());
+    mod_content.extend(GeneratorCompiler::compile(&self.grammar).into_iter());
     mod_content
   }
 
   fn compile_rules(&self) -> Vec<RItem> {
     self.grammar.rules.iter()
+      .filter(|rule| self.grammar.is_reachable_rule(rule.ident()))
       .flat_map(|&rule| RuleCompiler::compile(&self.grammar, rule).into_iter())
       .collect()
   }
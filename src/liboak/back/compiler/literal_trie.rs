@@ -0,0 +1,212 @@
+// Copyright 2017 Pierre Talbot (IRCAM)
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! A choice of string literals such as `"fn" / "for" / "if"` is compiled into a character trie: the input is read once to find the first literal of the choice which is a prefix of the input, instead of trying each literal in turn. The expectations of the literals tried before the matching one are recorded as if they were tried.
+//!
+//! When the grammar has a skip rule, each literal is followed by a call to the skip rule (see `middle::analysis::skip`). If the skip rule cannot fail, `"fn" skip / "for" skip` is equivalent to `("fn" / "for") skip` and it is compiled into a trie followed by the call.
+
+use back::compiler::*;
+use rust;
+use rust::AstBuilder;
+
+/// Minimal number of literals in a choice to compile it into a trie.
+const MIN_TRIE_LITERALS: usize = 4;
+
+pub struct LiteralTrieCompiler
+{
+  literals: Vec<String>,
+  suffix: Option<usize>
+}
+
+impl LiteralTrieCompiler
+{
+  /// The choice is compiled into a trie if its branches are literals, possibly followed by the same expression that cannot fail, usually a call to the skip rule or its inlined body.
+  pub fn recognizer(grammar: &TGrammar, choices: &Vec<usize>) -> Option<LiteralTrieCompiler> {
+    if choices.len() < MIN_TRIE_LITERALS {
+      return None;
+    }
+    let mut literals = vec![];
+    let mut suffixes = vec![];
+    for &branch in choices {
+      match split_literal(grammar, branch) {
+        Some((lit, suffix)) => {
+          literals.push(lit);
+          suffixes.push(suffix);
+        }
+        None => return None
+      }
+    }
+    let suffix = suffixes[0];
+    let same_suffix = suffixes.iter().all(|s| match (*s, suffix) {
+      (None, None) => true,
+      (Some(s), Some(suffix)) => never_failing_suffix(grammar, s) == never_failing_suffix(grammar, suffix),
+      _ => false
+    });
+    if !same_suffix {
+      return None;
+    }
+    Some(LiteralTrieCompiler {
+      literals: literals,
+      suffix: suffix
+    })
+  }
+
+  /// Index of the first literal matched in the input read from `stream`, the code returns an expression of type `Option<usize>`. The literals ending in `node` have a priority over the ones ending deeper only if they appear first in the choice.
+  fn compile_node(&self, cx: &ExtCtxt, stream: Ident, node: &TrieNode, best: Option<usize>) -> RExpr {
+    let best = match (best, node.end) {
+      (Some(best), Some(end)) => Some(::std::cmp::min(best, end)),
+      (best, end) => best.or(end)
+    };
+    let mut arms: Vec<_> = node.children.iter()
+      .filter(|&&(_, ref child)| best.map_or(true, |best| child.min_literal < best))
+      .map(|&(c, ref child)| {
+        let child = self.compile_node(cx, stream, child, best);
+        cx.arm(rust::DUMMY_SP, vec![quote_pat!(cx, Some($c))], child)
+      })
+      .collect();
+    let best_expr = match best {
+      Some(best) => quote_expr!(cx, Some($best)),
+      None => quote_expr!(cx, None)
+    };
+    if arms.is_empty() {
+      best_expr
+    }
+    else {
+      arms.push(cx.arm(rust::DUMMY_SP, vec![quote_pat!(cx, _)], best_expr));
+      cx.expr_match(rust::DUMMY_SP, quote_expr!(cx, $stream.next()), arms)
+    }
+  }
+}
+
+impl CompileExpr for LiteralTrieCompiler
+{
+  fn compile_expr<'a, 'b, 'c>(&self, context: &mut Context<'a, 'b, 'c>,
+    mut continuation: Continuation) -> RExpr
+  {
+    if let Some(suffix) = self.suffix {
+      continuation = continuation.compile_success(context, recognizer_compiler, suffix);
+    }
+    let cx = context.cx();
+    let mark = context.next_mark_name();
+    let stream = cx.ident_of("trie_stream");
+    let trie = TrieNode::new(&self.literals);
+    let matched = self.compile_node(cx, stream, &trie, None);
+    let literals = cx.expr_vec(rust::DUMMY_SP, self.literals.iter()
+      .map(|lit| {
+        let lit = lit.as_str();
+        quote_expr!(cx, $lit)
+      })
+      .collect());
+    let num_literals = self.literals.len();
+    continuation
+      .map_success(|success, failure| quote_expr!(cx, {
+        let $mark = state.mark();
+        let matched_literal = {
+          let mut $stream = $mark.clone();
+          $matched
+        };
+        let literals = $literals;
+        for literal in &literals[..matched_literal.unwrap_or($num_literals)] {
+          state.error(*literal);
+        }
+        match matched_literal {
+          Some(literal) => {
+            let mut state = state.restore($mark);
+            state.consume_prefix(literals[literal]);
+            $success
+          }
+          None => $failure
+        }
+      }))
+      .unwrap_success()
+  }
+}
+
+struct TrieNode
+{
+  /// Index of the literal ending at this node.
+  end: Option<usize>,
+  children: Vec<(char, TrieNode)>,
+  /// Smallest index of the literals ending in this node or below.
+  min_literal: usize
+}
+
+impl TrieNode
+{
+  fn new(literals: &Vec<String>) -> TrieNode {
+    let mut root = TrieNode::empty();
+    for (i, lit) in literals.iter().enumerate() {
+      root.insert(lit.chars().collect::<Vec<_>>().as_slice(), i);
+    }
+    root
+  }
+
+  fn empty() -> TrieNode {
+    TrieNode {
+      end: None,
+      children: vec![],
+      min_literal: usize::max_value()
+    }
+  }
+
+  fn insert(&mut self, lit: &[char], index: usize) {
+    self.min_literal = ::std::cmp::min(self.min_literal, index);
+    match lit.split_first() {
+      None => {
+        // A duplicated literal is never matched after its first occurrence.
+        if self.end.is_none() {
+          self.end = Some(index);
+        }
+      }
+      Some((&c, rest)) => {
+        let pos = match self.children.iter().position(|&(d, _)| d == c) {
+          Some(pos) => pos,
+          None => {
+            self.children.push((c, TrieNode::empty()));
+            self.children.len() - 1
+          }
+        };
+        self.children[pos].1.insert(rest, index);
+      }
+    }
+  }
+}
+
+/// `lit` or `lit suffix`, ignoring the type ascriptions and spans.
+fn split_literal(grammar: &TGrammar, expr: usize) -> Option<(String, Option<usize>)> {
+  match grammar.expr_by_index(expr) {
+    StrLiteral(lit) => Some((lit, None)),
+    Sequence(ref seq) if seq.len() == 2 => {
+      match split_literal(grammar, seq[0]) {
+        Some((lit, None)) if never_failing_suffix(grammar, seq[1]).is_some() => Some((lit, Some(seq[1]))),
+        _ => None
+      }
+    }
+      TypeAscription(child, _)
+    | SpannedExpr(child) => split_literal(grammar, child),
+    _ => None
+  }
+}
+
+/// The expressions `e*` and `e?`, possibly in the body of a called rule, never fail. Since the body of an inlined rule is copied at each call site, two suffixes are the same if they repeat the same expression `e`.
+fn never_failing_suffix(grammar: &TGrammar, expr: usize) -> Option<(bool, usize)> {
+  match grammar.expr_by_index(expr) {
+    ZeroOrMore(child) => Some((true, child)),
+    ZeroOrOne(child) => Some((false, child)),
+    NonTerminalSymbol(rule) => never_failing_suffix(grammar, grammar.expr_index_of_rule(rule)),
+      TypeAscription(child, _)
+    | SpannedExpr(child) => never_failing_suffix(grammar, child),
+    _ => None
+  }
+}
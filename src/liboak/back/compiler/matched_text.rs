@@ -0,0 +1,79 @@
+// Copyright 2017 Pierre Talbot (IRCAM)
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use back::compiler::*;
+
+pub enum TextKind
+{
+  /// `e -> &str`
+  Slice,
+  /// `e -> String`
+  Owned
+}
+
+/// The value of `e -> &str` is the slice of the stream matched by `e`, it is copied into a `String` with `e -> String`. Only the streams of the runtime implement `StreamSlice`, so on other streams the text of `e -> String` is copied character by character. The expression `e` is compiled as a recognizer.
+pub struct MatchedTextCompiler
+{
+  expr_idx: usize,
+  kind: TextKind
+}
+
+impl MatchedTextCompiler
+{
+  pub fn parser(expr_idx: usize, kind: TextKind) -> MatchedTextCompiler {
+    MatchedTextCompiler {
+      expr_idx: expr_idx,
+      kind: kind
+    }
+  }
+}
+
+impl CompileExpr for MatchedTextCompiler
+{
+  fn compile_expr<'a, 'b, 'c>(&self, context: &mut Context<'a, 'b, 'c>,
+    continuation: Continuation) -> RExpr
+  {
+    let lo_sp = context.next_mark_name();
+    let hi_sp = context.next_mark_name();
+    let result = context.next_free_var();
+    let cx = context.cx();
+    let slice = quote_expr!(cx, Range { start: $lo_sp, end: $hi_sp }.stream_slice());
+    let text = match self.kind {
+      TextKind::Slice => slice,
+      TextKind::Owned if context.grammar().is_runtime_stream() => quote_expr!(cx, $slice.to_string()),
+      TextKind::Owned => quote_expr!(cx, {
+        let mut text = String::new();
+        let mut $lo_sp = $lo_sp;
+        while $lo_sp < $hi_sp {
+          text.push($lo_sp.next().unwrap());
+        }
+        text
+      })
+    };
+    let matched_text = continuation
+      .map_success(|success, _| {
+        quote_expr!(cx, {
+          let $hi_sp = state.mark();
+          let $result = $text;
+          $success
+        })
+      })
+      .compile_success(context, recognizer_compiler, self.expr_idx)
+      .unwrap_success();
+    quote_expr!(cx, {
+      let $lo_sp = state.mark();
+      $matched_text
+    })
+  }
+}
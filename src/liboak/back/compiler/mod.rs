@@ -21,6 +21,7 @@ mod rule;
 mod str_literal;
 mod sequence;
 mod choice;
+mod literal_trie;
 mod any_single_char;
 mod repeat;
 mod optional;
@@ -28,13 +29,21 @@ mod syntactic_predicate;
 mod character_class;
 mod non_terminal;
 mod semantic_action;
+mod semantic_predicate;
 mod spanned_expr;
+mod cut;
+mod matched_text;
+mod ast_type;
+mod ast_visitor;
+mod printer;
+mod generator;
 
 pub use back::compiler::grammar::*;
 pub use back::context::*;
 use back::compiler::str_literal::*;
 use back::compiler::sequence::*;
 use back::compiler::choice::*;
+use back::compiler::literal_trie::*;
 use back::compiler::any_single_char::*;
 use back::compiler::repeat::*;
 use back::compiler::optional::*;
@@ -42,7 +51,10 @@ use back::compiler::syntactic_predicate::*;
 use back::compiler::character_class::*;
 use back::compiler::non_terminal::*;
 use back::compiler::semantic_action::*;
+use back::compiler::semantic_predicate::*;
 use back::compiler::spanned_expr::*;
+use back::compiler::cut::*;
+use back::compiler::matched_text::*;
 
 pub enum CompilerKind
 {
@@ -73,8 +85,10 @@ pub fn parser_compiler(grammar: &TGrammar, idx: usize) -> Box<CompileExpr> {
       OneOrMore(expr_idx) => Box::new(RepeatCompiler::parser(expr_idx, 1)),
       NonTerminalSymbol(id) => Box::new(NonTerminalCompiler::parser(id, idx)),
       SemanticAction(expr_idx, id) => Box::new(SemanticActionCompiler::parser(expr_idx, id, idx)),
-      TypeAscription(expr_idx, _) => parser_compiler(grammar, expr_idx),
+      SemanticPredicate(expr_idx, pred) => Box::new(SemanticPredicateCompiler::parser(expr_idx, pred, idx)),
+      TypeAscription(expr_idx, ty) => type_ascription_compiler(grammar, expr_idx, ty),
       SpannedExpr(expr_idx) => Box::new(SpannedExprCompiler::parser(expr_idx)),
+      Cut => Box::new(CutCompiler::parser()),
       NotPredicate(_)
     | AndPredicate(_) => unreachable!(
         "BUG: Syntactic predicate can not be compiled to parser (they do not generate data)."),
@@ -82,21 +96,47 @@ pub fn parser_compiler(grammar: &TGrammar, idx: usize) -> Box<CompileExpr> {
   }
 }
 
+/// The types `Vec<_>` and `Option<_>` are ascribed to `e*`, `e+` or `e?`, in which case `e` is directly compiled by the repeat or optional compiler so its value is built even if it is of unit type.
+fn type_ascription_compiler(grammar: &TGrammar, expr_idx: usize, ty: IType) -> Box<CompileExpr> {
+  match (ty, grammar.expr_by_index(expr_idx)) {
+    (IType::Regular(Type::Text), _) => Box::new(MatchedTextCompiler::parser(expr_idx, TextKind::Owned)),
+    (IType::Regular(Type::Slice), _) => Box::new(MatchedTextCompiler::parser(expr_idx, TextKind::Slice)),
+    (IType::Regular(Type::List(child)), ZeroOrMore(_)) => Box::new(RepeatCompiler::parser(child, 0)),
+    (IType::Regular(Type::List(child)), OneOrMore(_)) => Box::new(RepeatCompiler::parser(child, 1)),
+    (IType::Regular(Type::Optional(child)), ZeroOrOne(_)) => Box::new(OptionalCompiler::parser(child)),
+    _ => parser_compiler(grammar, expr_idx)
+  }
+}
+
 pub fn recognizer_compiler(grammar: &TGrammar, idx: usize) -> Box<CompileExpr> {
   match grammar.expr_by_index(idx) {
     StrLiteral(lit) => Box::new(StrLiteralCompiler::recognizer(lit)),
     CharacterClass(classes) => Box::new(CharacterClassCompiler::recognizer(classes)),
     AnySingleChar => Box::new(AnySingleCharCompiler::recognizer()),
     Sequence(seq) => Box::new(SequenceCompiler::recognizer(seq)),
-    Choice(choices) => Box::new(ChoiceCompiler::recognizer(choices)),
+    Choice(choices) => {
+      match LiteralTrieCompiler::recognizer(grammar, &choices) {
+        Some(trie) => Box::new(trie),
+        None => Box::new(ChoiceCompiler::recognizer(choices))
+      }
+    }
     ZeroOrOne(expr_idx) => Box::new(OptionalCompiler::recognizer(expr_idx)),
     ZeroOrMore(expr_idx) => Box::new(RepeatCompiler::recognizer(expr_idx, 0)),
     OneOrMore(expr_idx) => Box::new(RepeatCompiler::recognizer(expr_idx, 1)),
     NotPredicate(expr_idx) => Box::new(SyntacticPredicateCompiler::recognizer(expr_idx, Kind::Not)),
     AndPredicate(expr_idx) => Box::new(SyntacticPredicateCompiler::recognizer(expr_idx, Kind::And)),
     NonTerminalSymbol(id) => Box::new(NonTerminalCompiler::recognizer(id)),
-    SemanticAction(expr_idx, _) => recognizer_compiler(grammar, expr_idx),
+    SemanticAction(expr_idx, action) => {
+      if grammar.is_fallible_action(&action) {
+        Box::new(SemanticActionCompiler::recognizer(expr_idx, action, idx))
+      }
+      else {
+        recognizer_compiler(grammar, expr_idx)
+      }
+    }
+    SemanticPredicate(expr_idx, pred) => Box::new(SemanticPredicateCompiler::recognizer(expr_idx, pred, idx)),
     TypeAscription(expr_idx, _) => recognizer_compiler(grammar, expr_idx),
     SpannedExpr(expr_idx) => recognizer_compiler(grammar, expr_idx),
+    Cut => Box::new(CutCompiler::recognizer()),
   }
 }
@@ -0,0 +1,431 @@
+// Copyright 2017 Pierre Talbot (IRCAM)
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Generates the printers of a grammar annotated with `#![generate_printer]`.
+//!
+//! For each rule whose value retains the text parsed, we generate `write_<rule>(out, values..)` which walks the expression of the rule and writes the text corresponding to the values, and `print_<rule>(value) -> String`. Literals are written as they are, atoms with `Display`, and the text matched by `e -> String` or `$e` is written back. An expression of unit type is written with the shortest text it accepts, for example `[" "]*` is written as the empty string, except for calls to rules annotated with `#[print_separator = " "]` which are replaced by the separator.
+//!
+//! A rule cannot be printed if its value comes from a semantic action (except the constructors generated by `#![generate_ast]`), or if it is a choice between values that do not carry the branch taken.
+
+use middle::typing::ast::*;
+use middle::typing::ast::Type::*;
+use back::compiler::rtype::*;
+use back::compiler::ast_type::*;
+use rust;
+use rust::AstBuilder;
+use std::collections::{HashMap, HashSet};
+
+pub struct PrinterCompiler<'a: 'c, 'b: 'a, 'c>
+{
+  grammar: &'c TGrammar<'a, 'b>,
+  printable: HashSet<Ident>,
+  /// Fields of the structs built by the generated constructors.
+  struct_fields: HashMap<Ident, Vec<Ident>>,
+  /// Enum and variant built by the generated constructors.
+  enum_variants: HashMap<Ident, (Ident, Ident)>
+}
+
+impl<'a, 'b, 'c> PrinterCompiler<'a, 'b, 'c>
+{
+  pub fn compile(grammar: &'c TGrammar<'a, 'b>) -> Vec<RItem> {
+    if !grammar.attributes.generate_printer {
+      return vec![];
+    }
+    let mut compiler = PrinterCompiler::new(grammar);
+    compiler.remove_unprintable_rules();
+    grammar.rules.iter()
+      .filter(|rule| compiler.printable.contains(&rule.ident()))
+      .flat_map(|rule| compiler.compile_rule(*rule).into_iter())
+      .collect()
+  }
+
+  fn new(grammar: &'c TGrammar<'a, 'b>) -> Self {
+    let ast = AstTypeCompiler::new(grammar);
+    let mut struct_fields = HashMap::new();
+    let mut enum_variants = HashMap::new();
+    for rule in &grammar.rules {
+      match ast.shape(*rule) {
+        Some(AstShape::Struct { constructor, fields, .. }) => {
+          struct_fields.insert(constructor, fields.into_iter().map(|field| field.name).collect());
+        }
+        Some(AstShape::Enum { name, variants }) => {
+          for (variant, constructor, _) in variants {
+            enum_variants.insert(constructor, (name, variant));
+          }
+        }
+        None => ()
+      }
+    }
+    PrinterCompiler {
+      grammar: grammar,
+      printable: grammar.rules.iter()
+        .map(|rule| rule.ident())
+        .filter(|rule| grammar.is_reachable_rule(*rule))
+        .collect(),
+      struct_fields: struct_fields,
+      enum_variants: enum_variants
+    }
+  }
+
+  /// We start by assuming that every rule is printable and we remove the rules that cannot be printed until a fixpoint is reached.
+  fn remove_unprintable_rules(&mut self) {
+    loop {
+      let unprintable: Vec<Ident> = self.grammar.rules.iter()
+        .filter(|rule| self.printable.contains(&rule.ident()))
+        .filter(|rule| self.print_expr(rule.expr_idx, self.params(rule.expr_idx)).is_none())
+        .map(|rule| rule.ident())
+        .collect();
+      if unprintable.is_empty() {
+        break;
+      }
+      for rule in unprintable {
+        self.printable.remove(&rule);
+      }
+    }
+  }
+
+  fn compile_rule(&self, rule: Rule) -> Vec<RItem> {
+    let cx = self.cx();
+    let params = self.params(rule.expr_idx);
+    let body = self.print_expr(rule.expr_idx, params.clone())
+      .expect("The rule is printable.");
+    let write_fn = self.write_name(rule.ident());
+    let print_fn = string_to_ident(cx, format!("print_{}", rule.ident()));
+    let args: Vec<_> = params.iter().zip(self.param_types(rule.expr_idx).into_iter())
+      .map(|(param, ty)| {
+        let param = match param.node {
+          rust::ExprKind::Path(_, ref path) => path.segments[0].identifier,
+          _ => unreachable!()
+        };
+        cx.arg(self.span(), param, quote_ty!(cx, &$ty))
+      })
+      .collect();
+    let ty = TypeCompiler::compile(self.grammar, rule.expr_idx);
+    let (print_params, print_values) = match params.len() {
+      0 => (vec![], None),
+      1 => {
+        let value = params[0].clone();
+        (vec![quote_arg!(cx, value: &$ty)], quote_stmt!(cx, let $value = value;))
+      }
+      _ => {
+        let pat = self.ref_tuple_pat(&params);
+        (vec![quote_arg!(cx, value: &$ty)], quote_stmt!(cx, let $pat = *value;))
+      }
+    };
+    let mut write_args = vec![quote_expr!(cx, &mut out)];
+    write_args.extend(params.into_iter());
+    let write_call = cx.expr_call_ident(self.span(), write_fn, write_args);
+    let write_item = quote_item!(cx,
+      #[allow(unused_variables, dead_code)]
+      pub fn $write_fn<W: ::std::fmt::Write>(out: &mut W) -> ::std::fmt::Result {
+        $body;
+        Ok(())
+      }
+    ).expect("Quotation of a write function.");
+    let print_item = quote_item!(cx,
+      #[allow(dead_code)]
+      pub fn $print_fn() -> String {
+        let mut out = String::new();
+        $print_values
+        $write_call.expect("Writing into a string does not fail.");
+        out
+      }
+    ).expect("Quotation of a print function.");
+    vec![
+      self.with_signature(write_item, args),
+      self.with_signature(print_item, print_params)
+    ]
+  }
+
+  /// Add the parameters `args` and the generics of the stream type to `item`.
+  fn with_signature(&self, item: RItem, args: Vec<RArg>) -> RItem {
+    let stream_gen = self.grammar.stream_generics();
+    item.map(|mut item| {
+      if let rust::ItemKind::Fn(ref mut decl, _, _, _, ref mut generics, _) = item.node {
+        *decl = decl.clone().map(|mut decl| {
+          decl.inputs.extend(args.into_iter());
+          decl
+        });
+        generics.lifetimes = stream_gen.lifetimes.clone();
+        generics.ty_params.extend(stream_gen.ty_params.iter().cloned());
+      }
+      item
+    })
+  }
+
+  /// One parameter per component of the value of the expression.
+  fn params(&self, expr_idx: usize) -> Vec<RExpr> {
+    (0..self.cardinality(expr_idx))
+      .map(|i| {
+        let param = string_to_ident(self.cx(), format!("value_{}", i));
+        quote_expr!(self.cx(), $param)
+      })
+      .collect()
+  }
+
+  fn param_types(&self, expr_idx: usize) -> Vec<RTy> {
+    match self.grammar[expr_idx].ty.clone() {
+      Unit => vec![],
+      Tuple(indexes) => indexes.into_iter()
+        .map(|idx| TypeCompiler::compile(self.grammar, idx))
+        .collect(),
+      _ => vec![TypeCompiler::compile(self.grammar, expr_idx)]
+    }
+  }
+
+  fn cardinality(&self, expr_idx: usize) -> usize {
+    self.grammar[expr_idx].type_cardinality()
+  }
+
+  /// Writes the text of `expr_idx` from `values`, a reference to each component of its value. Returns `None` if the expression cannot be printed.
+  fn print_expr(&self, expr_idx: usize, values: Vec<RExpr>) -> Option<RExpr> {
+    let cx = self.cx();
+    if values.is_empty() {
+      return self.shortest_text(expr_idx, &mut HashSet::new())
+        .map(|text| self.write_str(text));
+    }
+    match self.grammar.expr_by_index(expr_idx) {
+      AnySingleChar
+    | CharacterClass(_) => {
+        let value = values[0].clone();
+        Some(quote_expr!(cx, out.write_char(*$value)?))
+      }
+      Sequence(children) => {
+        let mut values = values.into_iter();
+        let mut stmts = vec![];
+        for child in children {
+          let child_values = values.by_ref().take(self.cardinality(child)).collect();
+          stmts.push(cx.stmt_semi(self.print_expr(child, child_values)?));
+        }
+        Some(cx.expr_block(cx.block(self.span(), stmts)))
+      }
+      Choice(branches) => self.print_choice(expr_idx, branches, values[0].clone()),
+      ZeroOrMore(child)
+    | OneOrMore(child) => self.print_repeat(child, values[0].clone()),
+      ZeroOrOne(child) => self.print_optional(child, values[0].clone()),
+      NonTerminalSymbol(rule) => {
+        if self.printable.contains(&rule) {
+          let mut args = vec![quote_expr!(cx, out)];
+          args.extend(values.into_iter());
+          let write_call = cx.expr_call_ident(self.span(), self.write_name(rule), args);
+          Some(quote_expr!(cx, $write_call?))
+        }
+        else { None }
+      }
+      SemanticAction(child, RustAction::Constructor(constructor, _)) => {
+        self.struct_fields.get(&constructor).and_then(|fields| {
+          let value = values[0].clone();
+          let fields_values = fields.iter()
+            .map(|field| quote_expr!(cx, &$value.$field))
+            .collect();
+          self.print_expr(child, fields_values)
+        })
+      }
+      SemanticAction(..) => None,
+      SemanticPredicate(child, _) => self.print_expr(child, values),
+      TypeAscription(child, ty) => {
+        match ty {
+          IType::Regular(Text)
+        | IType::Regular(Slice) => {
+            let value = values[0].clone();
+            Some(quote_expr!(cx, out.write_str($value)?))
+          }
+          IType::Regular(List(repeated)) => self.print_repeat(repeated, values[0].clone()),
+          IType::Regular(Optional(optional)) => self.print_optional(optional, values[0].clone()),
+          _ => self.print_expr(child, values)
+        }
+      }
+      SpannedExpr(child) => self.print_expr(child, values.into_iter().skip(1).collect()),
+      StrLiteral(_)
+    | NotPredicate(_)
+    | AndPredicate(_)
+    | Cut => unreachable!("These expressions have the unit type.")
+    }
+  }
+
+  /// A choice is printed if each branch is a variant built by a generated constructor, or if every branch produces a character or a text that can be printed without knowing the branch taken: the unit parts of the branches, such as the `"\\"` of an escape sequence, must print nothing.
+  fn print_choice(&self, expr_idx: usize, branches: Vec<usize>, value: RExpr) -> Option<RExpr> {
+    let cx = self.cx();
+    let variants: Vec<_> = branches.iter()
+      .filter_map(|branch| match self.grammar.expr_by_index(*branch) {
+        SemanticAction(child, RustAction::Constructor(constructor, _)) =>
+          self.enum_variants.get(&constructor).map(|&(ty, variant)| (child, ty, variant)),
+        _ => None
+      })
+      .collect();
+    if variants.len() == branches.len() {
+      let mut arms = vec![];
+      for (child, ty, variant) in variants {
+        let fields = self.params(child);
+        let pat = self.variant_pat(ty, variant, &fields);
+        let print = self.print_expr(child, fields)?;
+        arms.push(cx.arm(self.span(), vec![pat], print));
+      }
+      Some(cx.expr_match(self.span(), quote_expr!(cx, *$value), arms))
+    }
+    else if branches.into_iter().all(|branch| self.has_empty_unit_text(branch, &mut HashSet::new())) {
+      match self.grammar[expr_idx].ty {
+        Atom => Some(quote_expr!(cx, out.write_char(*$value)?)),
+        Text | Slice => Some(quote_expr!(cx, out.write_str($value)?)),
+        _ => None
+      }
+    }
+    else { None }
+  }
+
+  /// True if the value of the expression is all its text: its unit sub-expressions print nothing.
+  fn has_empty_unit_text(&self, expr_idx: usize, visiting: &mut HashSet<Ident>) -> bool {
+    if self.cardinality(expr_idx) == 0 {
+      return self.shortest_text(expr_idx, visiting) == Some(String::new());
+    }
+    match self.grammar.expr_by_index(expr_idx) {
+      AnySingleChar
+    | CharacterClass(_) => true,
+      TypeAscription(child, ty) => {
+        match ty {
+          IType::Regular(Text)
+        | IType::Regular(Slice) => true,
+          _ => self.has_empty_unit_text(child, visiting)
+        }
+      }
+      Sequence(children) => children.into_iter()
+        .all(|child| self.has_empty_unit_text(child, visiting)),
+      Choice(branches) => branches.into_iter()
+        .all(|branch| self.has_empty_unit_text(branch, visiting)),
+      SemanticPredicate(child, _) => self.has_empty_unit_text(child, visiting),
+      NonTerminalSymbol(rule) => {
+        if visiting.insert(rule) {
+          let empty = self.has_empty_unit_text(self.grammar.expr_index_of_rule(rule), visiting);
+          visiting.remove(&rule);
+          empty
+        }
+        else { false }
+      }
+      _ => false
+    }
+  }
+
+  fn print_repeat(&self, child: usize, value: RExpr) -> Option<RExpr> {
+    let cx = self.cx();
+    let print = self.print_item(child)?;
+    Some(quote_expr!(cx, for item in $value.iter() { $print; }))
+  }
+
+  fn print_optional(&self, child: usize, value: RExpr) -> Option<RExpr> {
+    let cx = self.cx();
+    let print = self.print_item(child)?;
+    Some(quote_expr!(cx, if let Some(ref item) = *$value { $print; }))
+  }
+
+  /// Prints `child` with the value `item`, a reference to an element of a vector or of an option.
+  fn print_item(&self, child: usize) -> Option<RExpr> {
+    let cx = self.cx();
+    match self.cardinality(child) {
+      0 => self.print_expr(child, vec![]),
+      1 => self.print_expr(child, vec![quote_expr!(cx, item)]),
+      _ => {
+        let components = self.params(child);
+        let pat = self.ref_tuple_pat(&components);
+        let print = self.print_expr(child, components)?;
+        Some(quote_expr!(cx, {
+          let $pat = *item;
+          $print
+        }))
+      }
+    }
+  }
+
+  /// The shortest text accepted by an expression of unit type, `None` if there is none (such as for `.`).
+  fn shortest_text(&self, expr_idx: usize, visiting: &mut HashSet<Ident>) -> Option<String> {
+    match self.grammar.expr_by_index(expr_idx) {
+      StrLiteral(lit) => Some(lit),
+      AnySingleChar => None,
+      CharacterClass(class) => class.intervals.first().map(|interval| interval.lo.to_string()),
+      Sequence(children) => {
+        let mut text = String::new();
+        for child in children {
+          text.push_str(self.shortest_text(child, visiting)?.as_str());
+        }
+        Some(text)
+      }
+      Choice(branches) => branches.into_iter()
+        .filter_map(|branch| self.shortest_text(branch, visiting))
+        .min_by_key(|text| text.len()),
+      ZeroOrMore(_)
+    | ZeroOrOne(_)
+    | NotPredicate(_)
+    | AndPredicate(_)
+    | Cut => Some(String::new()),
+      OneOrMore(child)
+    | SemanticAction(child, _)
+    | SemanticPredicate(child, _)
+    | TypeAscription(child, _)
+    | SpannedExpr(child) => self.shortest_text(child, visiting),
+      NonTerminalSymbol(rule) => {
+        if let Some(separator) = self.grammar.rule_attributes(rule).print_separator {
+          Some(separator)
+        }
+        else if visiting.insert(rule) {
+          let text = self.shortest_text(self.grammar.expr_index_of_rule(rule), visiting);
+          visiting.remove(&rule);
+          text
+        }
+        else { None }
+      }
+    }
+  }
+
+  fn write_str(&self, text: String) -> RExpr {
+    let cx = self.cx();
+    if text.is_empty() {
+      quote_expr!(cx, ())
+    }
+    else {
+      let text = text.as_str();
+      quote_expr!(cx, out.write_str($text)?)
+    }
+  }
+
+  fn ref_tuple_pat(&self, components: &Vec<RExpr>) -> RPat {
+    let cx = self.cx();
+    cx.pat_tuple(self.span(), components.iter()
+      .map(|component| quote_pat!(cx, ref $component))
+      .collect())
+  }
+
+  fn variant_pat(&self, ty: Ident, variant: Ident, fields: &Vec<RExpr>) -> RPat {
+    let cx = self.cx();
+    let path = cx.path(self.span(), vec![ty, variant]);
+    if fields.is_empty() {
+      cx.pat_path(self.span(), path)
+    }
+    else {
+      cx.pat_tuple_struct(self.span(), path, fields.iter()
+        .map(|field| quote_pat!(cx, ref $field))
+        .collect())
+    }
+  }
+
+  fn write_name(&self, rule: Ident) -> Ident {
+    string_to_ident(self.cx(), format!("write_{}", rule))
+  }
+
+  fn span(&self) -> Span {
+    self.cx().call_site()
+  }
+
+  fn cx(&self) -> &'a ExtCtxt<'b> {
+    self.grammar.cx
+  }
+}
@@ -13,6 +13,7 @@
 // limitations under the License.
 
 use back::compiler::*;
+use back::compiler::character_class::class_condition;
 
 pub struct RepeatCompiler
 {
@@ -96,8 +97,71 @@ impl RepeatCompiler
   fn compile_recognizer<'a, 'b, 'c>(&self, context: &mut Context<'a, 'b, 'c>,
     continuation: Continuation) -> RExpr
   {
-    let body = context.compile_recognizer_expr(self.expr_idx);
-    self.compile(context, continuation, body)
+    match context.grammar().expr_by_index(self.expr_idx) {
+      CharacterClass(classes) if context.grammar().is_runtime_stream() => {
+        let desc = format!("{}", classes);
+        let x = context.cx().ident_of("x");
+        let condition = class_condition(context.cx(), &classes.intervals, x);
+        let scan = quote_expr!(context.cx(), state.consume_while(|$x| $condition));
+        self.compile_scan(context, continuation, scan, desc)
+      }
+      StrLiteral(lit) => {
+        let counter = context.next_counter_name();
+        let lit_str = lit.as_str();
+        let scan = quote_expr!(context.cx(), {
+          let mut $counter = 0;
+          while state.consume_prefix($lit_str) {
+            $counter += 1;
+          }
+          $counter
+        });
+        self.compile_scan(context, continuation, scan, lit.clone())
+      }
+      _ => {
+        let body = context.compile_recognizer_expr(self.expr_idx);
+        self.compile(context, continuation, body)
+      }
+    }
+  }
+
+  /// Repetitions of a single character class or literal are compiled into a scanning loop that does not create a mark for each iteration. The expression `scan` consumes the input and returns the number of matched items. We record the expectation `desc` of the last failed iteration, as the generic loop would.
+  fn compile_scan<'a, 'b, 'c>(&self, context: &mut Context<'a, 'b, 'c>,
+    continuation: Continuation, scan: RExpr, desc: String) -> RExpr
+  {
+    let mark = context.next_mark_name();
+    let counter = context.next_counter_name();
+    let cardinality_min = self.cardinality_min;
+    let desc = desc.as_str();
+    continuation.map_success(|success, failure|
+      if self.cardinality_min > 0 {
+        quote_expr!(context.cx(),
+          {
+            let $counter = $scan;
+            let $mark = state.mark();
+            state.error($desc);
+            if $counter < $cardinality_min {
+              $failure
+            }
+            else {
+              let mut state = state.restore_from_failure($mark);
+              $success
+            }
+          }
+        )
+      }
+      else {
+        quote_expr!(context.cx(),
+          {
+            $scan;
+            let $mark = state.mark();
+            state.error($desc);
+            let mut state = state.restore_from_failure($mark);
+            $success
+          }
+        )
+      }
+    )
+    .unwrap_success()
   }
 
   fn value_constructor(cx: &ExtCtxt, result_var: Ident, result_value: RExpr) -> RExpr {
@@ -45,6 +45,8 @@ impl<'a, 'b, 'c> TypeCompiler<'a, 'b, 'c>
       Optional(expr_idx) => self.optional_type(expr_idx),
       Action(rust_ty) => self.action_type(rust_ty),
       Tuple(indexes) => self.tuple_type(expr_idx, indexes),
+      Text => quote_ty!(self.grammar.cx, String),
+      Slice => self.grammar.slice_type()
     }
   }
 
@@ -45,6 +45,7 @@ impl<'a, 'b, 'c> RuleCompiler<'a, 'b, 'c>
 
     let body = context.compile(recognizer_compiler,
       self.expr(), success, failure);
+    let body = self.label_expectations(body);
 
     context.into_recognizer_function(body, self.rule)
   }
@@ -63,12 +64,29 @@ impl<'a, 'b, 'c> RuleCompiler<'a, 'b, 'c>
       let failure = quote_expr!(self.cx(), state.failure());
       let body = context.compile(parser_compiler,
         self.expr(), success, failure);
+      let body = self.label_expectations(body);
 
       context.close_scope(scope);
       context.into_parser_function(body, self.rule)
     }
   }
 
+  /// Rules annotated with `#[label = "..."]` replace their expectations by the label if they fail without consuming input.
+  fn label_expectations(&self, body: RExpr) -> RExpr {
+    match self.grammar.rule_attributes(self.rule.ident()).label {
+      None => body,
+      Some(label) => {
+        let label = label.as_str();
+        quote_expr!(self.cx(), {
+          let label_checkpoint = state.expectations_checkpoint();
+          let mut state = $body;
+          state.label_expectations(label_checkpoint, $label);
+          state
+        })
+      }
+    }
+  }
+
   fn parser_equals_recognizer(&self) -> bool {
     self.grammar[self.expr()].ty == Type::Unit
   }
@@ -13,22 +13,47 @@
 // limitations under the License.
 
 use back::compiler::*;
+use rust;
 use rust::AstBuilder;
 
 pub struct SemanticActionCompiler
 {
   expr_idx: usize,
-  action: Ident,
-  this_idx: usize
+  action: RustAction,
+  this_idx: usize,
+  compiler_kind: CompilerKind
 }
 
 impl SemanticActionCompiler
 {
-  pub fn parser(expr_idx: usize, action: Ident, this_idx: usize) -> SemanticActionCompiler {
+  pub fn parser(expr_idx: usize, action: RustAction, this_idx: usize) -> SemanticActionCompiler {
     SemanticActionCompiler {
       expr_idx: expr_idx,
       action: action,
-      this_idx: this_idx
+      this_idx: this_idx,
+      compiler_kind: CompilerKind::Parser
+    }
+  }
+
+  /// Only fallible actions are compiled in recognizers since they can make the expression fail.
+  pub fn recognizer(expr_idx: usize, action: RustAction, this_idx: usize) -> SemanticActionCompiler {
+    SemanticActionCompiler {
+      expr_idx: expr_idx,
+      action: action,
+      this_idx: this_idx,
+      compiler_kind: CompilerKind::Recognizer
+    }
+  }
+
+  fn action_call(&self, cx: &ExtCtxt, span: Span, args: Vec<RExpr>) -> RExpr {
+    match &self.action {
+        &RustAction::Function(ident)
+      | &RustAction::Constructor(ident, _) => cx.expr_call_ident(span, ident, args),
+      &RustAction::Path(ref path) => cx.expr_call(span, cx.expr_path(path.clone()), args),
+      &RustAction::Closure(ref closure) => {
+        let closure = cx.expr(span, rust::ExprKind::Paren(closure.clone()));
+        cx.expr_call(span, closure, args)
+      }
     }
   }
 }
@@ -39,19 +64,36 @@ impl CompileExpr for SemanticActionCompiler
     continuation: Continuation) -> RExpr
   {
     let cx = context.cx();
-    let result = context.next_free_var();
+    let result = match self.compiler_kind {
+      CompilerKind::Parser => context.next_free_var(),
+      CompilerKind::Recognizer => cx.ident_of("_result")
+    };
+    let fallible = context.grammar().is_fallible_action(&self.action);
     let scope = context.open_scope(self.expr_idx);
     let span = context.expr_span(self.this_idx);
     let args: Vec<_> = context.free_variables().into_iter()
       .map(|var| quote_expr!(cx, $var))
       .collect();
-    let action_call = cx.expr_call_ident(span, self.action, args);
+    let action_call = self.action_call(cx, span, args);
     let expr = continuation
-      .map_success(|success, _|
-        quote_expr!(cx, {
-          let $result = $action_call;
-          $success
-        })
+      .map_success(|success, failure|
+        if fallible {
+          quote_expr!(cx, {
+            match $action_call {
+              Ok($result) => $success,
+              Err(err) => {
+                state.error(format!("{}", err));
+                $failure
+              }
+            }
+          })
+        }
+        else {
+          quote_expr!(cx, {
+            let $result = $action_call;
+            $success
+          })
+        }
       )
       .compile_success(context, parser_compiler, self.expr_idx)
       .unwrap_success();
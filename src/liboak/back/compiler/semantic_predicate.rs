@@ -0,0 +1,97 @@
+// Copyright 2017 Pierre Talbot (IRCAM)
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use back::compiler::*;
+use rust;
+use rust::AstBuilder;
+
+/// A semantic predicate `e &{ pred }` calls `pred` with references to the values of `e`. It builds these values even in recognizers. When `pred` returns `false`, `<semantic predicate>` is expected.
+pub struct SemanticPredicateCompiler
+{
+  expr_idx: usize,
+  predicate: RExpr,
+  this_idx: usize,
+  compiler_kind: CompilerKind
+}
+
+impl SemanticPredicateCompiler
+{
+  pub fn recognizer(expr_idx: usize, predicate: RExpr, this_idx: usize) -> SemanticPredicateCompiler {
+    SemanticPredicateCompiler {
+      expr_idx: expr_idx,
+      predicate: predicate,
+      this_idx: this_idx,
+      compiler_kind: CompilerKind::Recognizer
+    }
+  }
+
+  pub fn parser(expr_idx: usize, predicate: RExpr, this_idx: usize) -> SemanticPredicateCompiler {
+    SemanticPredicateCompiler {
+      expr_idx: expr_idx,
+      predicate: predicate,
+      this_idx: this_idx,
+      compiler_kind: CompilerKind::Parser
+    }
+  }
+
+  fn compile_test<'a, 'b, 'c>(&self, context: &mut Context<'a, 'b, 'c>,
+    continuation: Continuation, values: Vec<Ident>) -> RExpr
+  {
+    let cx = context.cx();
+    let span = context.expr_span(self.this_idx);
+    let args: Vec<_> = values.into_iter()
+      .map(|var| quote_expr!(cx, &$var))
+      .collect();
+    let predicate = cx.expr(span, rust::ExprKind::Paren(self.predicate.clone()));
+    let test = cx.expr_call(span, predicate, args);
+    continuation
+      .map_success(|success, failure|
+        quote_expr!(cx,
+          if $test {
+            $success
+          }
+          else {
+            state.error("<semantic predicate>");
+            $failure
+          }
+        )
+      )
+      .compile_success(context, parser_compiler, self.expr_idx)
+      .unwrap_success()
+  }
+}
+
+impl CompileExpr for SemanticPredicateCompiler
+{
+  fn compile_expr<'a, 'b, 'c>(&self, context: &mut Context<'a, 'b, 'c>,
+    continuation: Continuation) -> RExpr
+  {
+    match self.compiler_kind {
+      CompilerKind::Parser => {
+        // The values of `expr_idx` are the values of this expression, they are bound to the last free variables.
+        let cardinality = context.expr_cardinality(self.expr_idx);
+        let free_vars = context.free_variables();
+        let values = free_vars[free_vars.len()-cardinality..].to_vec();
+        self.compile_test(context, continuation, values)
+      }
+      CompilerKind::Recognizer => {
+        let scope = context.open_scope(self.expr_idx);
+        let values = context.free_variables();
+        let expr = self.compile_test(context, continuation, values);
+        context.close_scope(scope);
+        expr
+      }
+    }
+  }
+}
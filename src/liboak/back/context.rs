@@ -29,6 +29,8 @@ pub struct Context<'a: 'c, 'b: 'a, 'c>
   name_factory: NameFactory,
   free_variables: Vec<Ident>,
   mut_ref_free_variables: Vec<(Ident, RTy)>,
+  /// Cut variable of the enclosing choice branches, `None` if the branch does not need one (such as the last branch).
+  cut_variables: Vec<Option<Ident>>,
   num_combinators_compiled: usize
 }
 
@@ -42,6 +44,7 @@ impl<'a, 'b, 'c> Context<'a, 'b, 'c>
       name_factory: NameFactory::new(),
       free_variables: vec![],
       mut_ref_free_variables: vec![],
+      cut_variables: vec![],
       num_combinators_compiled: 0
     }
   }
@@ -49,14 +52,14 @@ impl<'a, 'b, 'c> Context<'a, 'b, 'c>
   pub fn into_recognizer_function(self, body: RExpr, rule: Rule) -> RItem {
     let cx = self.cx();
     let recognizer_fn = recognizer_name(cx, rule.ident());
-    self.function(recognizer_fn, true, body, quote_ty!(cx, ()))
+    self.function(rule, recognizer_fn, true, body, quote_ty!(cx, ()))
   }
 
   pub fn into_parser_alias(self, rule: Rule) -> RItem {
     let cx = self.cx();
     let recognizer_fn = recognizer_name(cx, rule.ident());
     let parser_fn = parser_name(cx, rule.ident());
-    self.function(parser_fn, false,
+    self.function(rule, parser_fn, false,
       quote_expr!(cx, $recognizer_fn(state)),
       quote_ty!(cx, ()))
   }
@@ -64,10 +67,10 @@ impl<'a, 'b, 'c> Context<'a, 'b, 'c>
   pub fn into_parser_function(self, body: RExpr, rule: Rule) -> RItem {
     let parser_fn = parser_name(self.cx(), rule.ident());
     let ty = TypeCompiler::compile(self.grammar, rule.expr_idx);
-    self.function(parser_fn, true, body, ty)
+    self.function(rule, parser_fn, true, body, ty)
   }
 
-  fn function(self, name: Ident, state_mut: bool, body: RExpr, ty: RTy) -> RItem {
+  fn function(self, rule: Rule, name: Ident, state_mut: bool, body: RExpr, ty: RTy) -> RItem {
     let cx = self.cx();
     let state_param = self.state_param(state_mut);
     let stream_ty = self.grammar.stream_type();
@@ -85,12 +88,22 @@ impl<'a, 'b, 'c> Context<'a, 'b, 'c>
       generics.lifetimes = stream_gen.lifetimes;
       generics.ty_params = stream_gen.ty_params;
       generics.where_clause = stream_gen.where_clause;
+      let mut attrs = self.rule_attributes(rule, fun.attrs.clone());
+      // Only entry rules are public when the grammar declares some of them.
+      let vis =
+        if self.grammar.is_public_rule(rule.ident()) {
+          fun.vis.clone()
+        }
+        else {
+          attrs.push(quote_attr!(cx, #[allow(dead_code)]));
+          rust::Visibility::Inherited
+        };
       let item = rust::Item {
         ident: fun.ident,
-        attrs: fun.attrs.clone(),
+        attrs: attrs,
         id: fun.id,
         node: rust::ItemKind::Fn(a,b,c,d,generics,f),
-        vis: fun.vis.clone(),
+        vis: vis,
         span: fun.span,
         tokens: None
       };
@@ -98,6 +111,29 @@ impl<'a, 'b, 'c> Context<'a, 'b, 'c>
     } else { unreachable!() }
   }
 
+  /// The generated function carries the attributes of the rule, its documentation followed by the grammar text of its body. The default `#[inline]` is dropped if the rule has its own `inline` attribute.
+  fn rule_attributes(&self, rule: Rule, default_attrs: Vec<rust::Attribute>) -> Vec<rust::Attribute> {
+    let cx = self.cx();
+    let rust_attrs = self.grammar.rule_attributes(rule.ident()).rust_attributes;
+    let mut attrs: Vec<_> = if rust_attrs.iter().any(|attr| attr.check_name("inline")) {
+      default_attrs.into_iter().filter(|attr| !attr.check_name("inline")).collect()
+    } else {
+      default_attrs
+    };
+    let (docs, others): (Vec<_>, Vec<_>) = rust_attrs.into_iter()
+      .partition(|attr| attr.check_name("doc"));
+    attrs.extend(docs.into_iter());
+    let body_span = self.grammar[rule.expr_idx].span();
+    if let Ok(body) = cx.codemap().span_to_snippet(body_span) {
+      let doc = format!("\n```text\n{} = {}\n```", rule.ident(), body);
+      let doc = cx.meta_name_value(body_span, rust::Symbol::intern("doc"),
+        rust::LitKind::Str(rust::Symbol::intern(doc.as_str()), rust::StrStyle::Cooked));
+      attrs.push(cx.attribute(body_span, doc));
+    }
+    attrs.extend(others.into_iter());
+    attrs
+  }
+
   #[allow(unused_imports)] // `quote_tokens` generates a warning.
   fn state_param(&self, state_mut: bool) -> RArg {
     let mut_kw = if state_mut {
@@ -221,6 +257,29 @@ impl<'a, 'b, 'c> Context<'a, 'b, 'c>
     self.name_factory.next_branch_failed_name(cx)
   }
 
+  pub fn next_first_char_name(&mut self) -> Ident {
+    let cx = self.cx();
+    self.name_factory.next_first_char_name(cx)
+  }
+
+  pub fn next_cut_name(&mut self) -> Ident {
+    let cx = self.cx();
+    self.name_factory.next_cut_name(cx)
+  }
+
+  pub fn push_cut_var(&mut self, cut_var: Option<Ident>) {
+    self.cut_variables.push(cut_var);
+  }
+
+  pub fn pop_cut_var(&mut self) {
+    self.cut_variables.pop()
+      .expect("There is no cut variables.");
+  }
+
+  pub fn current_cut_var(&self) -> Option<Ident> {
+    self.cut_variables.last().cloned().and_then(|cut| cut)
+  }
+
   pub fn next_free_var(&mut self) -> Ident {
     self.free_variables.pop().expect("Free variables are all bound.")
   }
@@ -283,6 +342,10 @@ impl<'a, 'b, 'c> Context<'a, 'b, 'c>
     self.free_variables = scope.free_variables;
   }
 
+  pub fn grammar(&self) -> &'c TGrammar<'a, 'b> {
+    self.grammar
+  }
+
   pub fn cx(&self) -> &'a ExtCtxt<'b> {
     &self.grammar.cx
   }
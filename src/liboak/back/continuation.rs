@@ -58,6 +58,10 @@ impl Continuation
     self
   }
 
+  pub fn failure(&self) -> RExpr {
+    self.failure.clone()
+  }
+
   pub fn unwrap_success(self) -> RExpr {
     self.success
   }
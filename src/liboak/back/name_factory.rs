@@ -28,8 +28,10 @@ pub struct NameFactory
   prefix_uid: usize,
   mark_uid: usize,
   branch_failed_uid: usize,
+  cut_uid: usize,
   counter_uid: usize,
-  closure_uid: usize
+  closure_uid: usize,
+  first_char_uid: usize
 }
 
 impl NameFactory
@@ -39,8 +41,10 @@ impl NameFactory
       prefix_uid: 1,
       mark_uid: 0,
       branch_failed_uid: 0,
+      cut_uid: 0,
       counter_uid: 0,
-      closure_uid: 0
+      closure_uid: 0,
+      first_char_uid: 0
     }
   }
 
@@ -54,6 +58,11 @@ impl NameFactory
     string_to_ident(cx, format!("branch_failed_{}", self.branch_failed_uid))
   }
 
+  pub fn next_cut_name(&mut self, cx: &ExtCtxt) -> Ident {
+    self.cut_uid += 1;
+    string_to_ident(cx, format!("cut{}", self.cut_uid))
+  }
+
   pub fn next_closure_name(&mut self, cx: &ExtCtxt) -> Ident {
     self.closure_uid += 1;
     string_to_ident(cx, format!("success_continuation_{}", self.closure_uid))
@@ -64,6 +73,11 @@ impl NameFactory
     string_to_ident(cx, format!("counter{}", self.counter_uid))
   }
 
+  pub fn next_first_char_name(&mut self, cx: &ExtCtxt) -> Ident {
+    self.first_char_uid += 1;
+    string_to_ident(cx, format!("first_char{}", self.first_char_uid))
+  }
+
   pub fn fresh_vars(&mut self, cx: &ExtCtxt, cardinality: usize) -> Vec<Ident> {
     let prefix = self.next_var_prefix();
     (0..cardinality)
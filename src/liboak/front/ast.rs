@@ -15,6 +15,8 @@
 pub use rust::{Spanned, BytePos, NO_EXPANSION};
 pub use ast::*;
 
+use std::collections::HashMap;
+
 pub struct FGrammar
 {
   pub name: Ident,
@@ -22,7 +24,10 @@ pub struct FGrammar
   pub exprs: Vec<Expression>,
   pub exprs_info: Vec<FExpressionInfo>,
   pub rust_items: Vec<RItem>,
-  pub attributes: Vec<Attribute>
+  pub attributes: Vec<Attribute>,
+  pub rules_attributes: HashMap<Ident, Vec<Attribute>>,
+  /// Types declared by the user with `rule: Type = e`.
+  pub rules_types: HashMap<Ident, RTy>
 }
 
 impl FGrammar
@@ -34,7 +39,9 @@ impl FGrammar
       exprs: vec![],
       exprs_info: vec![],
       rust_items: vec![],
-      attributes: vec![]
+      attributes: vec![],
+      rules_attributes: HashMap::new(),
+      rules_types: HashMap::new()
     }
   }
 
@@ -45,10 +52,17 @@ impl FGrammar
     expr_idx
   }
 
-  pub fn push_rule(&mut self, name: SpannedIdent, def: usize) {
+  pub fn push_rule(&mut self, name: SpannedIdent, def: usize, attrs: Vec<Attribute>) {
+    self.rules_attributes.entry(name.node)
+      .or_insert_with(|| vec![])
+      .extend(attrs.into_iter());
     self.rules.push(Rule::new(name, def));
   }
 
+  pub fn push_rule_type(&mut self, name: Ident, ty: RTy) {
+    self.rules_types.insert(name, ty);
+  }
+
   pub fn push_attr(&mut self, attr: Attribute) {
     self.attributes.push(attr);
   }
@@ -0,0 +1,249 @@
+// Copyright 2017 Pierre Talbot (IRCAM)
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Translation of the grammars written in ABNF ([RFC 5234](https://tools.ietf.org/html/rfc5234) and the case-sensitive strings of [RFC 7405](https://tools.ietf.org/html/rfc7405)). The choices of ABNF are not ordered, the branches are tried in the order of the file except for the choices of literals which are tried from the longest to the shortest. Rule names are case-insensitive in ABNF, they are translated in lowercase. The core rules (`ALPHA`, `DIGIT`, ...) are added when they are used. The prose values `<...>` cannot be translated.
+
+use front::import::*;
+use front::import::GExpr::*;
+use std::ascii::AsciiExt;
+
+pub fn import(source: &str) -> ImportResult<ImportedGrammar> {
+  AbnfImporter::new(source).import()
+}
+
+struct AbnfImporter
+{
+  cursor: Cursor,
+  grammar: ImportedGrammar
+}
+
+impl AbnfImporter
+{
+  fn new(source: &str) -> AbnfImporter {
+    AbnfImporter {
+      cursor: Cursor::new(source),
+      grammar: ImportedGrammar::new()
+    }
+  }
+
+  fn import(mut self) -> ImportResult<ImportedGrammar> {
+    self.skip_blanks();
+    while !self.cursor.at_end() {
+      self.parse_rule()?;
+      self.skip_blanks();
+    }
+    self.grammar.declare_predefined_rules(core_rules());
+    let undeclared = self.grammar.undeclared_rules();
+    if let Some(rule) = undeclared.first() {
+      return Err(format!("the rule `{}` is not declared.", rule));
+    }
+    Ok(self.grammar)
+  }
+
+  fn skip_blanks(&mut self) {
+    loop {
+      self.cursor.skip_whitespaces();
+      if self.cursor.starts_with(";") {
+        self.cursor.skip_line();
+      }
+      else {
+        return;
+      }
+    }
+  }
+
+  /// A rule starts with its name followed by `=` or `=/`, it is how we know where the previous rule stops.
+  fn at_rule_start(&mut self) -> bool {
+    let start = self.cursor.position();
+    let is_start = self.parse_rulename().is_ok() && {
+      self.skip_blanks();
+      self.cursor.starts_with("=")
+    };
+    self.cursor.reset(start);
+    is_start
+  }
+
+  fn parse_rulename(&mut self) -> ImportResult<String> {
+    match self.cursor.peek() {
+      Some(c) if c <= '\x7f' && c.is_alphabetic() => {
+        let name = self.cursor.take_while(|c| c <= '\x7f' && (c.is_alphanumeric() || c == '-'));
+        Ok(name.to_lowercase())
+      }
+      _ => self.cursor.error(format!("expected a rule name."))
+    }
+  }
+
+  /// `name = elements` or `name =/ elements` which adds alternatives to an existing rule.
+  fn parse_rule(&mut self) -> ImportResult<()> {
+    let name = self.parse_rulename()?;
+    self.skip_blanks();
+    self.cursor.expect("=")?;
+    let incremental = self.cursor.eat("/");
+    let expr = self.parse_alternation()?;
+    match (incremental, self.grammar.is_declared(name.as_str())) {
+      (false, true) => self.cursor.error(format!("the rule `{}` is declared twice.", name)),
+      (true, false) => self.cursor.error(format!("alternatives are added with `=/` to the undeclared rule `{}`.", name)),
+      (false, false) => {
+        self.grammar.push_rule(name, expr);
+        Ok(())
+      }
+      (true, true) => {
+        let rule = self.grammar.find_rule(name.as_str()).unwrap();
+        let mut branches = match rule.expr.clone() {
+          Choice(branches, _) => branches,
+          expr => vec![expr]
+        };
+        match expr {
+          Choice(alternatives, _) => branches.extend(alternatives.into_iter()),
+          expr => branches.push(expr)
+        }
+        rule.expr = Choice(branches, false);
+        Ok(())
+      }
+    }
+  }
+
+  fn parse_alternation(&mut self) -> ImportResult<GExpr> {
+    let mut branches = vec![self.parse_concatenation()?];
+    self.skip_blanks();
+    while self.cursor.eat("/") {
+      branches.push(self.parse_concatenation()?);
+      self.skip_blanks();
+    }
+    Ok(if branches.len() == 1 { branches.pop().unwrap() } else { Choice(branches, false) })
+  }
+
+  fn parse_concatenation(&mut self) -> ImportResult<GExpr> {
+    let mut elements = vec![self.parse_repetition()?];
+    loop {
+      self.skip_blanks();
+      match self.cursor.peek() {
+        None | Some('/') | Some(')') | Some(']') => break,
+        _ if self.at_rule_start() => break,
+        _ => elements.push(self.parse_repetition()?)
+      }
+    }
+    Ok(if elements.len() == 1 { elements.pop().unwrap() } else { Sequence(elements) })
+  }
+
+  /// `n*m element`, `n element`, the bounds are optional.
+  fn parse_repetition(&mut self) -> ImportResult<GExpr> {
+    self.skip_blanks();
+    let location = self.cursor.location();
+    let min = self.cursor.number(10).map(|n| n as usize);
+    let bounds = if self.cursor.eat("*") {
+      let max = self.cursor.number(10).map(|n| n as usize);
+      Some((min.unwrap_or(0), max))
+    }
+    else {
+      min.map(|n| (n, Some(n)))
+    };
+    let element = self.parse_element()?;
+    Ok(match bounds {
+      None => element,
+      Some((min, max)) => GExpr::repeat(element, min, max, location, &mut self.grammar.warnings)
+    })
+  }
+
+  fn parse_element(&mut self) -> ImportResult<GExpr> {
+    self.skip_blanks();
+    match self.cursor.peek() {
+      Some('(') => {
+        self.cursor.bump();
+        let expr = self.parse_alternation()?;
+        self.cursor.expect(")")?;
+        Ok(expr)
+      }
+      Some('[') => {
+        self.cursor.bump();
+        let expr = self.parse_alternation()?;
+        self.cursor.expect("]")?;
+        Ok(GExpr::optional(expr))
+      }
+      Some('"') => Ok(CaselessLiteral(self.parse_string()?)),
+      Some('%') => self.parse_percent(),
+      Some('<') => self.cursor.error(format!("the prose values `<...>` cannot be translated.")),
+      Some(_) => Ok(Rule(self.parse_rulename()?)),
+      None => self.cursor.error(format!("expected an element but found the end of the file."))
+    }
+  }
+
+  fn parse_string(&mut self) -> ImportResult<String> {
+    self.cursor.expect("\"")?;
+    let lit = self.cursor.take_while(|c| c != '"' && c != '\n');
+    self.cursor.expect("\"")?;
+    Ok(lit)
+  }
+
+  /// `%s"..."`, `%i"..."` and the numeric values `%x41`, `%x41-5A` and `%x0D.0A`.
+  fn parse_percent(&mut self) -> ImportResult<GExpr> {
+    let location = self.cursor.location();
+    self.cursor.expect("%")?;
+    let radix = match self.cursor.bump().map(|c| c.to_ascii_lowercase()) {
+      Some('s') => return Ok(Literal(self.parse_string()?)),
+      Some('i') => return Ok(CaselessLiteral(self.parse_string()?)),
+      Some('x') => 16,
+      Some('d') => 10,
+      Some('b') => 2,
+      _ => return self.cursor.error(format!("expected `s`, `i`, `x`, `d` or `b` after `%`."))
+    };
+    let lo = self.parse_num_char(radix)?;
+    if self.cursor.eat("-") {
+      let hi = self.parse_num_char(radix)?;
+      GExpr::class(vec![(lo, hi)], false, location)
+    }
+    else {
+      let mut lit = lo.to_string();
+      while self.cursor.eat(".") {
+        lit.push(self.parse_num_char(radix)?);
+      }
+      Ok(Literal(lit))
+    }
+  }
+
+  fn parse_num_char(&mut self, radix: u32) -> ImportResult<char> {
+    match self.cursor.number(radix).and_then(::std::char::from_u32) {
+      Some(c) => Ok(c),
+      None => self.cursor.error(format!("expected a character code."))
+    }
+  }
+}
+
+fn class(intervals: &[(char, char)]) -> GExpr {
+  Class(intervals.to_vec())
+}
+
+/// The core rules of the appendix B of the RFC 5234.
+fn core_rules() -> Vec<(&'static str, GExpr)> {
+  let rule = |name: &str| Rule(String::from(name));
+  vec![
+    ("alpha", class(&[('A', 'Z'), ('a', 'z')])),
+    ("bit", class(&[('0', '1')])),
+    ("char", class(&[('\x01', '\x7f')])),
+    ("cr", Literal(format!("\r"))),
+    ("crlf", Literal(format!("\r\n"))),
+    ("ctl", class(&[('\0', '\x1f'), ('\x7f', '\x7f')])),
+    ("digit", class(&[('0', '9')])),
+    ("dquote", Literal(format!("\""))),
+    ("hexdig", class(&[('0', '9'), ('A', 'F'), ('a', 'f')])),
+    ("htab", Literal(format!("\t"))),
+    ("lf", Literal(format!("\n"))),
+    ("lwsp", GExpr::Repeat(Box::new(Choice(vec![
+      rule("wsp"), Sequence(vec![rule("crlf"), rule("wsp")])], true)), 0, None)),
+    ("octet", class(&[('\0', '\u{ff}')])),
+    ("sp", Literal(format!(" "))),
+    ("vchar", class(&[('\x21', '\x7e')])),
+    ("wsp", class(&[(' ', ' '), ('\t', '\t')]))
+  ]
+}
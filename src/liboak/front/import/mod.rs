@@ -0,0 +1,643 @@
+// Copyright 2017 Pierre Talbot (IRCAM)
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Translation of grammars written for other tools (pest, ABNF and the EBNF of the W3C) into the syntax of Oak.
+//!
+//! A grammar is imported with the attributes `#![import_pest = "path"]`, `#![import_abnf = "path"]` or `#![import_w3c_ebnf = "path"]`. The file is read into an `ImportedGrammar`, printed with the syntax of Oak, and the parser of Oak parses the result as if the rules were written in the grammar. Since these formats do not have semantic actions, the imported rules are of type `(^)`. The constructs that cannot be translated are reported as errors, and the ones translated approximately (for example the exceptions `A - B` of the W3C when the characters of `A` are unknown, or the unordered choices which are not choices of literals) as warnings.
+
+use std::fs::File;
+use std::io::Read;
+use std::ascii::AsciiExt;
+
+mod pest;
+mod abnf;
+mod w3c_ebnf;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ImportFormat
+{
+  Pest,
+  Abnf,
+  W3cEbnf
+}
+
+impl ImportFormat
+{
+  pub fn from_attribute(name: &str) -> Option<ImportFormat> {
+    use self::ImportFormat::*;
+    match name {
+      "import_pest" => Some(Pest),
+      "import_abnf" => Some(Abnf),
+      "import_w3c_ebnf" => Some(W3cEbnf),
+      _ => None
+    }
+  }
+}
+
+/// Errors are located with the line and column in the imported file.
+pub type ImportResult<T> = Result<T, String>;
+
+pub fn import_file(format: ImportFormat, path: &str) -> ImportResult<ImportedGrammar> {
+  let mut source = String::new();
+  File::open(path)
+    .and_then(|mut file| file.read_to_string(&mut source))
+    .map_err(|err| format!("Cannot read the imported grammar `{}`: {}.", path, err))?;
+  let grammar = match format {
+    ImportFormat::Pest => pest::import(source.as_str()),
+    ImportFormat::Abnf => abnf::import(source.as_str()),
+    ImportFormat::W3cEbnf => w3c_ebnf::import(source.as_str())
+  };
+  grammar
+    .map(|mut grammar| { grammar.warn_unordered_choices(); grammar })
+    .map_err(|err| format!("{}:{}", path, err))
+}
+
+pub struct ImportedGrammar
+{
+  pub rules: Vec<ImportedRule>,
+  pub skip_rule: Option<String>,
+  pub warnings: Vec<String>
+}
+
+pub struct ImportedRule
+{
+  pub name: String,
+  pub expr: GExpr,
+  pub no_skip: bool
+}
+
+impl ImportedGrammar
+{
+  pub fn new() -> ImportedGrammar {
+    ImportedGrammar {
+      rules: vec![],
+      skip_rule: None,
+      warnings: vec![]
+    }
+  }
+
+  pub fn push_rule(&mut self, name: String, expr: GExpr) {
+    self.rules.push(ImportedRule {
+      name: name,
+      expr: expr,
+      no_skip: false
+    });
+  }
+
+  pub fn find_rule(&mut self, name: &str) -> Option<&mut ImportedRule> {
+    self.rules.iter_mut().find(|rule| rule.name == name)
+  }
+
+  pub fn is_declared(&self, name: &str) -> bool {
+    self.rules.iter().any(|rule| rule.name == name)
+  }
+
+  /// Rules called but not declared in the grammar.
+  pub fn undeclared_rules(&self) -> Vec<String> {
+    let mut calls = vec![];
+    for rule in &self.rules {
+      rule.expr.collect_calls(&mut calls);
+    }
+    calls.into_iter().filter(|rule| !self.is_declared(rule.as_str())).collect()
+  }
+
+  /// Adds the predefined rules `(name, expr)` of the format which are called but not declared.
+  pub fn declare_predefined_rules(&mut self, predefined: Vec<(&str, GExpr)>) {
+    loop {
+      let undeclared = self.undeclared_rules();
+      let mut added = false;
+      for &(name, ref expr) in &predefined {
+        if undeclared.iter().any(|rule| rule == name) {
+          self.push_rule(String::from(name), expr.clone());
+          added = true;
+        }
+      }
+      if !added { break; }
+    }
+  }
+
+  /// The exception `A - B` is translated as `!(B !C) A` where `C` is the class of the characters matched by `A`: `B` is rejected if it matches a text that `A` cannot extend, which is the case of the keywords excluded from identifiers. If the characters of `A` are not known, for example if `A` contains `.`, it is translated as `!B A` and a warning is reported.
+  pub fn translate_exceptions(&mut self) {
+    let mut warnings = vec![];
+    let exprs: Vec<_> = self.rules.iter()
+      .map(|rule| self.translate_exception(rule.expr.clone(), &rule.name, &mut warnings))
+      .collect();
+    for (rule, expr) in self.rules.iter_mut().zip(exprs.into_iter()) {
+      rule.expr = expr;
+    }
+    self.warnings.extend(warnings.into_iter());
+  }
+
+  fn translate_exception(&self, expr: GExpr, rule: &String, warnings: &mut Vec<String>) -> GExpr {
+    use self::GExpr::*;
+    match expr {
+      Sequence(children) => Sequence(children.into_iter()
+        .map(|child| self.translate_exception(child, rule, warnings)).collect()),
+      Choice(branches, ordered) => Choice(branches.into_iter()
+        .map(|branch| self.translate_exception(branch, rule, warnings)).collect(), ordered),
+      Repeat(child, min, max) => Repeat(Box::new(self.translate_exception(*child, rule, warnings)), min, max),
+      Not(child) => Not(Box::new(self.translate_exception(*child, rule, warnings))),
+      And(child) => And(Box::new(self.translate_exception(*child, rule, warnings))),
+      Exception(expr, except) => {
+        let expr = self.translate_exception(*expr, rule, warnings);
+        let except = self.translate_exception(*except, rule, warnings);
+        let except = match self.characters(&expr, &mut vec![]) {
+          // `A` only matches the empty text, `!(B !C)` is `!B`.
+          Some(ref chars) if chars.is_empty() => except,
+          Some(mut chars) => {
+            chars.sort();
+            chars.dedup();
+            Sequence(vec![except, Not(Box::new(Class(chars)))])
+          }
+          None => {
+            warnings.push(format!(
+              "in the rule `{}`, the exception `A - B` is translated as `!B A`, it rejects the inputs of `A` starting with a text matched by `B`.",
+              rule));
+            except
+          }
+        };
+        Sequence(vec![Not(Box::new(except)), expr])
+      }
+      expr => expr
+    }
+  }
+
+  /// Intervals of the characters matched by `expr`, `None` if it can match any character.
+  fn characters(&self, expr: &GExpr, visited: &mut Vec<String>) -> Option<Vec<(char, char)>> {
+    use self::GExpr::*;
+    match expr {
+      &Literal(ref lit) => Some(lit.chars().map(|c| (c, c)).collect()),
+      &CaselessLiteral(ref lit) => Some(lit.chars()
+        .flat_map(|c| vec![(c.to_ascii_lowercase(), c.to_ascii_lowercase()),
+          (c.to_ascii_uppercase(), c.to_ascii_uppercase())].into_iter())
+        .collect()),
+      &Class(ref intervals) => Some(intervals.clone()),
+      &NegatedClass(_)
+    | &Any => None,
+      &Rule(ref name) => {
+        if visited.contains(name) {
+          return Some(vec![]);
+        }
+        visited.push(name.clone());
+        self.rules.iter().find(|rule| rule.name == *name)
+          .and_then(|rule| self.characters(&rule.expr, visited))
+      }
+      &Sequence(ref children)
+    | &Choice(ref children, _) => {
+        let mut chars = vec![];
+        for child in children {
+          match self.characters(child, visited) {
+            Some(child_chars) => chars.extend(child_chars.into_iter()),
+            None => return None
+          }
+        }
+        Some(chars)
+      }
+      &Repeat(ref child, _, _)
+    | &Exception(ref child, _) => self.characters(child, visited),
+      &Not(_)
+    | &And(_) => Some(vec![])
+    }
+  }
+
+  /// Since the branches of the unordered choices are tried in the order of the file, a branch is not tried if a previous one matches. The choices of literals are reordered by `GExpr::order_branches` and the other ones are reported.
+  fn warn_unordered_choices(&mut self) {
+    let mut warnings = vec![];
+    for rule in &self.rules {
+      rule.expr.warn_unordered_choices(&rule.name, &mut warnings);
+    }
+    self.warnings.extend(warnings.into_iter());
+  }
+
+  /// The grammar in the syntax of Oak, every rule is public since these formats do not distinguish the entry points.
+  pub fn to_oak(&self) -> String {
+    let mut out = String::new();
+    if let Some(ref skip_rule) = self.skip_rule {
+      out.push_str(format!("#![skip = \"{}\"]\n", oak_name(skip_rule)).as_str());
+    }
+    for rule in &self.rules {
+      if rule.no_skip {
+        out.push_str("#[no_skip]\n");
+      }
+      out.push_str(format!("pub {} = ({}) -> (^)\n", oak_name(&rule.name), rule.expr.to_oak()).as_str());
+    }
+    out
+  }
+}
+
+/// Number of copies of `e?` generated for the upper bound of a repetition, beyond it the bound is ignored.
+const MAX_EXPANSION: usize = 16;
+
+/// Expression common to the imported formats.
+#[derive(Clone, Debug)]
+pub enum GExpr
+{
+  Literal(String),
+  /// The ASCII letters of the literal match both cases.
+  CaselessLiteral(String),
+  Class(Vec<(char, char)>),
+  NegatedClass(Vec<(char, char)>),
+  Any,
+  Rule(String),
+  Sequence(Vec<GExpr>),
+  /// Choices are ordered in pest but not in ABNF and W3C EBNF.
+  Choice(Vec<GExpr>, bool),
+  /// `Repeat(e, min, max)`.
+  Repeat(Box<GExpr>, usize, Option<usize>),
+  Not(Box<GExpr>),
+  And(Box<GExpr>),
+  /// `A - B` of the W3C EBNF, replaced by `ImportedGrammar::translate_exceptions` once every rule is declared.
+  Exception(Box<GExpr>, Box<GExpr>)
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Precedence
+{
+  Choice,
+  Sequence,
+  Prefix,
+  Suffix,
+  Atom
+}
+
+impl GExpr
+{
+  pub fn empty() -> GExpr {
+    GExpr::Sequence(vec![])
+  }
+
+  pub fn optional(expr: GExpr) -> GExpr {
+    GExpr::Repeat(Box::new(expr), 0, Some(1))
+  }
+
+  /// `e{min, max}`, the upper bound is dropped with a warning if it is too large to be expanded.
+  pub fn repeat(expr: GExpr, min: usize, max: Option<usize>, location: String, warnings: &mut Vec<String>) -> GExpr {
+    let max = match max {
+      Some(max) if max < min => Some(min),
+      Some(max) if max - min > MAX_EXPANSION => {
+        warnings.push(format!("{}: the upper bound {} of the repetition is ignored.", location, max));
+        None
+      }
+      max => max
+    };
+    match (min, max) {
+      (1, Some(1)) => expr,
+      _ => GExpr::Repeat(Box::new(expr), min, max)
+    }
+  }
+
+  /// `[intervals]`, or `[^intervals]` if `negated`. Oak has no empty class, so a class without characters, such as `[]` or `%x5A-41`, is an error.
+  pub fn class(intervals: Vec<(char, char)>, negated: bool, location: String) -> ImportResult<GExpr> {
+    if let Some(&(lo, hi)) = intervals.iter().find(|&&(lo, hi)| lo > hi) {
+      return Err(format!("{}: the interval {:?}-{:?} of the class is empty.", location, lo, hi));
+    }
+    match (intervals.is_empty(), negated) {
+      (true, false) => Err(format!("{}: the class does not contain any character.", location)),
+      (true, true) => Ok(GExpr::Any),
+      (false, false) => Ok(GExpr::Class(intervals)),
+      (false, true) => Ok(GExpr::NegatedClass(intervals))
+    }
+  }
+
+  fn warn_unordered_choices(&self, rule: &String, warnings: &mut Vec<String>) {
+    use self::GExpr::*;
+    match self {
+      &Choice(ref branches, ordered) => {
+        if !ordered && branches.len() > 1 && !branches.iter().all(|branch| literal_len(branch).is_some()) {
+          warnings.push(format!(
+            "in the rule `{}`, the branches of the unordered choice are tried in the order of the file, a branch is not tried if a previous one matches.",
+            rule));
+        }
+        for branch in branches {
+          branch.warn_unordered_choices(rule, warnings);
+        }
+      }
+      &Sequence(ref children) => {
+        for child in children {
+          child.warn_unordered_choices(rule, warnings);
+        }
+      }
+      &Repeat(ref child, _, _)
+    | &Not(ref child)
+    | &And(ref child) => child.warn_unordered_choices(rule, warnings),
+      &Exception(ref expr, ref except) => {
+        expr.warn_unordered_choices(rule, warnings);
+        except.warn_unordered_choices(rule, warnings);
+      }
+      _ => ()
+    }
+  }
+
+  fn collect_calls(&self, calls: &mut Vec<String>) {
+    use self::GExpr::*;
+    match self {
+      &Rule(ref name) => {
+        if !calls.contains(name) {
+          calls.push(name.clone());
+        }
+      }
+      &Sequence(ref children)
+    | &Choice(ref children, _) => {
+        for child in children {
+          child.collect_calls(calls);
+        }
+      }
+      &Repeat(ref child, _, _)
+    | &Not(ref child)
+    | &And(ref child) => child.collect_calls(calls),
+      &Exception(ref expr, ref except) => {
+        expr.collect_calls(calls);
+        except.collect_calls(calls);
+      }
+      _ => ()
+    }
+  }
+
+  pub fn to_oak(&self) -> String {
+    self.print().0
+  }
+
+  fn print(&self) -> (String, Precedence) {
+    use self::GExpr::*;
+    match self {
+      &Literal(ref lit) => (oak_literal(lit), Precedence::Atom),
+      &CaselessLiteral(ref lit) => GExpr::caseless(lit).print(),
+      &Class(ref intervals) => (oak_class(intervals), Precedence::Atom),
+      &NegatedClass(ref intervals) => (format!("!{} .", oak_class(intervals)), Precedence::Sequence),
+      &Any => (format!("."), Precedence::Atom),
+      &Rule(ref name) => (oak_name(name), Precedence::Atom),
+      &Sequence(ref children) => {
+        match children.len() {
+          0 => (format!("\"\""), Precedence::Atom),
+          1 => children[0].print(),
+          _ => {
+            let children: Vec<_> = children.iter()
+              .map(|child| child.print_at_least(Precedence::Prefix))
+              .collect();
+            (children.join(" "), Precedence::Sequence)
+          }
+        }
+      }
+      &Choice(ref branches, ordered) => {
+        if branches.len() == 1 {
+          return branches[0].print();
+        }
+        let mut branches = branches.clone();
+        if !ordered {
+          GExpr::order_branches(&mut branches);
+        }
+        let branches: Vec<_> = branches.iter()
+          .map(|branch| branch.print_at_least(Precedence::Sequence))
+          .collect();
+        (branches.join(" / "), Precedence::Choice)
+      }
+      &Repeat(ref child, min, max) => {
+        let child = child.print_at_least(Precedence::Atom);
+        match (min, max) {
+          (0, None) => (format!("{}*", child), Precedence::Suffix),
+          (1, None) => (format!("{}+", child), Precedence::Suffix),
+          (0, Some(1)) => (format!("{}?", child), Precedence::Suffix),
+          (0, Some(0)) => (format!("\"\""), Precedence::Atom),
+          (min, max) => {
+            let mut copies = vec![child.clone(); min];
+            match max {
+              None => copies.push(format!("{}*", child)),
+              Some(max) => copies.extend(vec![format!("{}?", child); max - min].into_iter())
+            }
+            (copies.join(" "), Precedence::Sequence)
+          }
+        }
+      }
+      &Not(ref child) => (format!("!{}", child.print_at_least(Precedence::Suffix)), Precedence::Prefix),
+      &And(ref child) => (format!("&{}", child.print_at_least(Precedence::Suffix)), Precedence::Prefix),
+      &Exception(..) => unreachable!("Exceptions are translated before printing the grammar.")
+    }
+  }
+
+  fn print_at_least(&self, prec: Precedence) -> String {
+    let (expr, expr_prec) = self.print();
+    if expr_prec < prec { format!("({})", expr) }
+    else { expr }
+  }
+
+  /// The ASCII letters are replaced by a class of both cases, for example `["aA"]`.
+  fn caseless(lit: &String) -> GExpr {
+    let mut parts = vec![];
+    let mut text = String::new();
+    for c in lit.chars() {
+      let lower = c.to_ascii_lowercase();
+      let upper = c.to_ascii_uppercase();
+      if lower != upper {
+        if !text.is_empty() {
+          parts.push(GExpr::Literal(text.clone()));
+          text.clear();
+        }
+        parts.push(GExpr::Class(vec![(lower, lower), (upper, upper)]));
+      }
+      else {
+        text.push(c);
+      }
+    }
+    if !text.is_empty() {
+      parts.push(GExpr::Literal(text));
+    }
+    GExpr::Sequence(parts)
+  }
+
+  /// In an unordered choice of literals, a literal must be tried before the literals it is a prefix of, so the longest literals are tried first. The other choices are kept in the order of the file.
+  fn order_branches(branches: &mut Vec<GExpr>) {
+    if branches.iter().all(|branch| literal_len(branch).is_some()) {
+      branches.sort_by(|a, b| literal_len(b).cmp(&literal_len(a)));
+    }
+  }
+}
+
+fn literal_len(expr: &GExpr) -> Option<usize> {
+  match expr {
+    &GExpr::Literal(ref lit)
+  | &GExpr::CaselessLiteral(ref lit) => Some(lit.chars().count()),
+    _ => None
+  }
+}
+
+fn oak_literal(lit: &String) -> String {
+  format!("{:?}", lit)
+}
+
+/// The character `-` is placed at the beginning of the class since it separates the bounds of the intervals.
+fn oak_class(intervals: &Vec<(char, char)>) -> String {
+  let mut dash = false;
+  let mut content = String::new();
+  for &(lo, hi) in intervals {
+    if lo <= '-' && '-' <= hi {
+      dash = true;
+      if lo < '-' { push_interval(&mut content, lo, ','); }
+      if '-' < hi { push_interval(&mut content, '.', hi); }
+    }
+    else {
+      push_interval(&mut content, lo, hi);
+    }
+  }
+  if dash {
+    content.insert(0, '-');
+  }
+  format!("[{:?}]", content)
+}
+
+fn push_interval(content: &mut String, lo: char, hi: char) {
+  content.push(lo);
+  if lo != hi {
+    content.push('-');
+    content.push(hi);
+  }
+}
+
+/// Rule names are turned into Rust identifiers.
+pub fn oak_name(name: &String) -> String {
+  let mut ident: String = name.chars()
+    .map(|c| if c.is_alphanumeric() || c == '_' { c } else { '_' })
+    .collect();
+  if ident.chars().next().map_or(true, |c| c.is_numeric()) {
+    ident.insert_str(0, "r_");
+  }
+  if RUST_KEYWORDS.contains(&ident.as_str()) {
+    ident.push('_');
+  }
+  ident
+}
+
+static RUST_KEYWORDS: &'static [&'static str] = &[
+  "abstract", "alignof", "as", "become", "box", "break", "const", "continue", "crate", "do",
+  "else", "enum", "extern", "false", "final", "fn", "for", "if", "impl", "in", "let", "loop",
+  "macro", "match", "mod", "move", "mut", "offsetof", "override", "priv", "proc", "pub", "pure",
+  "ref", "return", "self", "Self", "sizeof", "static", "struct", "super", "trait", "true", "type",
+  "typeof", "unsafe", "unsized", "use", "virtual", "where", "while", "yield"];
+
+/// Reads the imported file character by character.
+pub struct Cursor
+{
+  chars: Vec<char>,
+  pos: usize
+}
+
+impl Cursor
+{
+  pub fn new(source: &str) -> Cursor {
+    Cursor {
+      chars: source.chars().collect(),
+      pos: 0
+    }
+  }
+
+  pub fn peek(&self) -> Option<char> {
+    self.peek_nth(0)
+  }
+
+  pub fn peek_nth(&self, n: usize) -> Option<char> {
+    self.chars.get(self.pos + n).cloned()
+  }
+
+  pub fn bump(&mut self) -> Option<char> {
+    let c = self.peek();
+    if c.is_some() {
+      self.pos += 1;
+    }
+    c
+  }
+
+  pub fn at_end(&self) -> bool {
+    self.pos >= self.chars.len()
+  }
+
+  pub fn starts_with(&self, prefix: &str) -> bool {
+    prefix.chars().enumerate().all(|(i, c)| self.peek_nth(i) == Some(c))
+  }
+
+  pub fn eat(&mut self, prefix: &str) -> bool {
+    if self.starts_with(prefix) {
+      self.pos += prefix.chars().count();
+      true
+    }
+    else { false }
+  }
+
+  pub fn expect(&mut self, prefix: &str) -> ImportResult<()> {
+    if self.eat(prefix) { Ok(()) }
+    else {
+      let found = self.peek().map_or(format!("the end of the file"), |c| format!("`{}`", c));
+      self.error(format!("expected `{}` but found {}.", prefix, found))
+    }
+  }
+
+  pub fn take_while<P>(&mut self, pred: P) -> String where
+   P: Fn(char) -> bool
+  {
+    let mut taken = String::new();
+    while let Some(c) = self.peek() {
+      if !pred(c) { break; }
+      taken.push(c);
+      self.pos += 1;
+    }
+    taken
+  }
+
+  pub fn skip_whitespaces(&mut self) {
+    self.take_while(char::is_whitespace);
+  }
+
+  /// Skips the characters until the end of the line (included).
+  pub fn skip_line(&mut self) {
+    self.take_while(|c| c != '\n');
+    self.bump();
+  }
+
+  /// Skips the characters until `end` (included), it is used for block comments.
+  pub fn skip_until(&mut self, end: &str) -> ImportResult<()> {
+    while !self.eat(end) {
+      if self.bump().is_none() {
+        return self.error(format!("unterminated comment, expected `{}`.", end));
+      }
+    }
+    Ok(())
+  }
+
+  pub fn number(&mut self, radix: u32) -> Option<u32> {
+    let digits = self.take_while(|c| c.is_digit(radix));
+    u32::from_str_radix(digits.as_str(), radix).ok()
+  }
+
+  pub fn position(&self) -> usize {
+    self.pos
+  }
+
+  pub fn reset(&mut self, pos: usize) {
+    self.pos = pos;
+  }
+
+  /// `line:column` of the current position.
+  pub fn location(&self) -> String {
+    let before = &self.chars[..self.pos];
+    let line = before.iter().filter(|c| **c == '\n').count() + 1;
+    let column = before.iter().rev().take_while(|c| **c != '\n').count() + 1;
+    format!("{}:{}", line, column)
+  }
+
+  pub fn warning(&self, msg: String) -> String {
+    format!("{}: {}", self.location(), msg)
+  }
+
+  pub fn error<T>(&self, msg: String) -> ImportResult<T> {
+    Err(format!("{}: {}", self.location(), msg))
+  }
+}
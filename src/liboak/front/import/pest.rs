@@ -0,0 +1,313 @@
+// Copyright 2017 Pierre Talbot (IRCAM)
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Translation of the grammars of [pest](https://pest.rs). The rules `_{...}` are translated as normal rules, and the atomic rules `@{...}` and `${...}` are annotated with `#[no_skip]`. If `WHITESPACE` or `COMMENT` is defined, the rule `pest_skip` repeating them becomes the skip rule of the grammar; note that Oak skips the blanks after every atom while pest skips them between the elements of sequences and repetitions. The stack operations (`PUSH`, `POP`, ...) cannot be translated.
+
+use front::import::*;
+use front::import::GExpr::*;
+
+pub fn import(source: &str) -> ImportResult<ImportedGrammar> {
+  PestImporter::new(source).import()
+}
+
+struct PestImporter
+{
+  cursor: Cursor,
+  grammar: ImportedGrammar
+}
+
+impl PestImporter
+{
+  fn new(source: &str) -> PestImporter {
+    PestImporter {
+      cursor: Cursor::new(source),
+      grammar: ImportedGrammar::new()
+    }
+  }
+
+  fn import(mut self) -> ImportResult<ImportedGrammar> {
+    self.skip_blanks()?;
+    while !self.cursor.at_end() {
+      self.parse_rule()?;
+      self.skip_blanks()?;
+    }
+    self.declare_skip_rule();
+    self.grammar.declare_predefined_rules(builtin_rules());
+    let undeclared = self.grammar.undeclared_rules();
+    if let Some(rule) = undeclared.first() {
+      return Err(format!("the rule `{}` is not declared.", rule));
+    }
+    Ok(self.grammar)
+  }
+
+  fn declare_skip_rule(&mut self) {
+    let blanks: Vec<_> = ["WHITESPACE", "COMMENT"].iter()
+      .filter(|rule| self.grammar.is_declared(rule))
+      .map(|rule| Rule(String::from(*rule)))
+      .collect();
+    if !blanks.is_empty() {
+      let skip = String::from("pest_skip");
+      let expr = GExpr::Repeat(Box::new(Choice(blanks, true)), 0, None);
+      self.grammar.push_rule(skip.clone(), expr);
+      self.grammar.skip_rule = Some(skip);
+    }
+  }
+
+  fn skip_blanks(&mut self) -> ImportResult<()> {
+    loop {
+      self.cursor.skip_whitespaces();
+      if self.cursor.starts_with("//") {
+        self.cursor.skip_line();
+      }
+      else if self.cursor.eat("/*") {
+        self.cursor.skip_until("*/")?;
+      }
+      else {
+        return Ok(());
+      }
+    }
+  }
+
+  fn parse_ident(&mut self) -> ImportResult<String> {
+    let ident = self.cursor.take_while(|c| c.is_alphanumeric() || c == '_');
+    if ident.is_empty() {
+      self.cursor.error(format!("expected a rule name."))
+    }
+    else {
+      Ok(ident)
+    }
+  }
+
+  /// `name = modifier? { expr }`
+  fn parse_rule(&mut self) -> ImportResult<()> {
+    let name = self.parse_ident()?;
+    self.skip_blanks()?;
+    self.cursor.expect("=")?;
+    self.skip_blanks()?;
+    let no_skip = match self.cursor.peek() {
+      Some('@') | Some('$') => { self.cursor.bump(); true }
+      Some('_') | Some('!') => { self.cursor.bump(); false }
+      _ => false
+    };
+    self.skip_blanks()?;
+    self.cursor.expect("{")?;
+    let expr = self.parse_choice()?;
+    self.cursor.expect("}")?;
+    if self.grammar.is_declared(name.as_str()) {
+      return self.cursor.error(format!("the rule `{}` is declared twice.", name));
+    }
+    self.grammar.push_rule(name, expr);
+    self.grammar.rules.last_mut().unwrap().no_skip = no_skip;
+    Ok(())
+  }
+
+  fn parse_choice(&mut self) -> ImportResult<GExpr> {
+    let mut branches = vec![self.parse_sequence()?];
+    while self.cursor.eat("|") {
+      branches.push(self.parse_sequence()?);
+    }
+    Ok(if branches.len() == 1 { branches.pop().unwrap() } else { Choice(branches, true) })
+  }
+
+  fn parse_sequence(&mut self) -> ImportResult<GExpr> {
+    let mut elements = vec![self.parse_prefix()?];
+    while self.cursor.eat("~") {
+      elements.push(self.parse_prefix()?);
+    }
+    Ok(if elements.len() == 1 { elements.pop().unwrap() } else { Sequence(elements) })
+  }
+
+  fn parse_prefix(&mut self) -> ImportResult<GExpr> {
+    self.skip_blanks()?;
+    if self.cursor.eat("&") {
+      Ok(And(Box::new(self.parse_prefix()?)))
+    }
+    else if self.cursor.eat("!") {
+      Ok(Not(Box::new(self.parse_prefix()?)))
+    }
+    else {
+      self.parse_postfix()
+    }
+  }
+
+  fn parse_postfix(&mut self) -> ImportResult<GExpr> {
+    let mut expr = self.parse_atom()?;
+    loop {
+      self.skip_blanks()?;
+      let location = self.cursor.location();
+      expr = match self.cursor.peek() {
+        Some('*') => { self.cursor.bump(); GExpr::Repeat(Box::new(expr), 0, None) }
+        Some('+') => { self.cursor.bump(); GExpr::Repeat(Box::new(expr), 1, None) }
+        Some('?') => { self.cursor.bump(); GExpr::optional(expr) }
+        Some('{') => {
+          self.cursor.bump();
+          let (min, max) = self.parse_bounds()?;
+          GExpr::repeat(expr, min, max, location, &mut self.grammar.warnings)
+        }
+        _ => return Ok(expr)
+      };
+    }
+  }
+
+  /// `{n}`, `{n,}`, `{,m}` and `{n,m}`, the opening brace is already consumed.
+  fn parse_bounds(&mut self) -> ImportResult<(usize, Option<usize>)> {
+    self.skip_blanks()?;
+    let min = self.cursor.number(10).map(|n| n as usize);
+    self.skip_blanks()?;
+    let bounds = if self.cursor.eat(",") {
+      self.skip_blanks()?;
+      let max = self.cursor.number(10).map(|n| n as usize);
+      (min.unwrap_or(0), max)
+    }
+    else if min.is_some() {
+      (min.unwrap(), min)
+    }
+    else {
+      return self.cursor.error(format!("expected the bounds of a repetition."));
+    };
+    self.skip_blanks()?;
+    self.cursor.expect("}")?;
+    Ok(bounds)
+  }
+
+  fn parse_atom(&mut self) -> ImportResult<GExpr> {
+    self.skip_blanks()?;
+    match self.cursor.peek() {
+      Some('(') => {
+        self.cursor.bump();
+        let expr = self.parse_choice()?;
+        self.skip_blanks()?;
+        self.cursor.expect(")")?;
+        Ok(expr)
+      }
+      Some('"') => Ok(Literal(self.parse_string()?)),
+      Some('^') => {
+        self.cursor.bump();
+        Ok(CaselessLiteral(self.parse_string()?))
+      }
+      Some('\'') => {
+        let location = self.cursor.location();
+        let lo = self.parse_char()?;
+        self.skip_blanks()?;
+        if self.cursor.eat("..") {
+          self.skip_blanks()?;
+          let hi = self.parse_char()?;
+          GExpr::class(vec![(lo, hi)], false, location)
+        }
+        else {
+          Ok(Literal(lo.to_string()))
+        }
+      }
+      Some('#') => {
+        let location = self.cursor.location();
+        self.cursor.bump();
+        let tag = self.parse_ident()?;
+        self.skip_blanks()?;
+        self.cursor.expect("=")?;
+        self.grammar.warnings.push(format!("{}: the tag `#{}` is ignored.", location, tag));
+        self.parse_prefix()
+      }
+      Some(c) if c.is_alphabetic() || c == '_' => {
+        let location = self.cursor.location();
+        let name = self.parse_ident()?;
+        match name.as_str() {
+          "PUSH" | "POP" | "POP_ALL" | "PEEK" | "PEEK_ALL" | "DROP" => {
+            Err(format!("{}: the stack operation `{}` of pest cannot be translated.", location, name))
+          }
+          "SOI" => {
+            self.grammar.warnings.push(format!("{}: `SOI` is translated as the empty expression, it only holds at the start of the input.", location));
+            Ok(GExpr::empty())
+          }
+          "EOI" => Ok(Not(Box::new(Any))),
+          "ANY" => Ok(Any),
+          _ => Ok(Rule(name))
+        }
+      }
+      _ => self.cursor.error(format!("expected an expression."))
+    }
+  }
+
+  fn parse_string(&mut self) -> ImportResult<String> {
+    self.cursor.expect("\"")?;
+    let mut lit = String::new();
+    loop {
+      match self.cursor.bump() {
+        Some('"') => return Ok(lit),
+        Some('\\') => lit.push(self.parse_escape()?),
+        Some(c) => lit.push(c),
+        None => return self.cursor.error(format!("unterminated string literal."))
+      }
+    }
+  }
+
+  fn parse_char(&mut self) -> ImportResult<char> {
+    self.cursor.expect("'")?;
+    let c = match self.cursor.bump() {
+      Some('\\') => self.parse_escape()?,
+      Some(c) => c,
+      None => return self.cursor.error(format!("unterminated character literal."))
+    };
+    self.cursor.expect("'")?;
+    Ok(c)
+  }
+
+  fn parse_escape(&mut self) -> ImportResult<char> {
+    match self.cursor.bump() {
+      Some('n') => Ok('\n'),
+      Some('r') => Ok('\r'),
+      Some('t') => Ok('\t'),
+      Some('0') => Ok('\0'),
+      Some('x') => {
+        let code = self.cursor.number(16);
+        self.code_point(code)
+      }
+      Some('u') => {
+        self.cursor.expect("{")?;
+        let code = self.cursor.number(16);
+        self.cursor.expect("}")?;
+        self.code_point(code)
+      }
+      Some(c) => Ok(c),
+      None => self.cursor.error(format!("unterminated escape sequence."))
+    }
+  }
+
+  fn code_point(&self, code: Option<u32>) -> ImportResult<char> {
+    match code.and_then(::std::char::from_u32) {
+      Some(c) => Ok(c),
+      None => self.cursor.error(format!("invalid character code in escape sequence."))
+    }
+  }
+}
+
+fn class(intervals: &[(char, char)]) -> GExpr {
+  Class(intervals.to_vec())
+}
+
+/// The builtin rules of pest, declared when they are used.
+fn builtin_rules() -> Vec<(&'static str, GExpr)> {
+  vec![
+    ("NEWLINE", Choice(vec![Literal(format!("\n")), Literal(format!("\r\n")), Literal(format!("\r"))], true)),
+    ("ASCII_DIGIT", class(&[('0', '9')])),
+    ("ASCII_NONZERO_DIGIT", class(&[('1', '9')])),
+    ("ASCII_BIN_DIGIT", class(&[('0', '1')])),
+    ("ASCII_OCT_DIGIT", class(&[('0', '7')])),
+    ("ASCII_HEX_DIGIT", class(&[('0', '9'), ('a', 'f'), ('A', 'F')])),
+    ("ASCII_ALPHA_LOWER", class(&[('a', 'z')])),
+    ("ASCII_ALPHA_UPPER", class(&[('A', 'Z')])),
+    ("ASCII_ALPHA", class(&[('a', 'z'), ('A', 'Z')])),
+    ("ASCII_ALPHANUMERIC", class(&[('a', 'z'), ('A', 'Z'), ('0', '9')])),
+    ("ASCII", class(&[('\0', '\x7f')]))
+  ]
+}
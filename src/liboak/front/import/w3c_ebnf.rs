@@ -0,0 +1,223 @@
+// Copyright 2017 Pierre Talbot (IRCAM)
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Translation of the EBNF notation of the [XML specification](https://www.w3.org/TR/xml/#sec-notation). The choices are not ordered, the branches are tried in the order of the file except for the choices of literals which are tried from the longest to the shortest. The exception `A - B` is translated once every rule is read, see `ImportedGrammar::translate_exceptions`.
+
+use front::import::*;
+use front::import::GExpr::*;
+
+pub fn import(source: &str) -> ImportResult<ImportedGrammar> {
+  W3cImporter::new(source).import()
+}
+
+struct W3cImporter
+{
+  cursor: Cursor,
+  grammar: ImportedGrammar
+}
+
+impl W3cImporter
+{
+  fn new(source: &str) -> W3cImporter {
+    W3cImporter {
+      cursor: Cursor::new(source),
+      grammar: ImportedGrammar::new()
+    }
+  }
+
+  fn import(mut self) -> ImportResult<ImportedGrammar> {
+    self.skip_blanks()?;
+    while !self.cursor.at_end() {
+      self.parse_rule()?;
+      self.skip_blanks()?;
+    }
+    self.grammar.translate_exceptions();
+    let undeclared = self.grammar.undeclared_rules();
+    if let Some(rule) = undeclared.first() {
+      return Err(format!("the rule `{}` is not declared.", rule));
+    }
+    Ok(self.grammar)
+  }
+
+  fn skip_blanks(&mut self) -> ImportResult<()> {
+    loop {
+      self.cursor.skip_whitespaces();
+      if self.cursor.eat("/*") {
+        self.cursor.skip_until("*/")?;
+      }
+      else {
+        return Ok(());
+      }
+    }
+  }
+
+  /// A rule starts with its name (possibly preceded by a number `[1]`) followed by `::=`.
+  fn at_rule_start(&mut self) -> bool {
+    let start = self.cursor.position();
+    let is_start = self.skip_production_number() && self.parse_name().is_ok() && {
+      let _ = self.skip_blanks();
+      self.cursor.starts_with("::=")
+    };
+    self.cursor.reset(start);
+    is_start
+  }
+
+  /// Skips the optional number `[n]` of a production, returns `false` if the brackets do not contain a number.
+  fn skip_production_number(&mut self) -> bool {
+    let start = self.cursor.position();
+    if self.cursor.eat("[") {
+      let numbered = self.cursor.number(10).is_some() && self.cursor.eat("]");
+      if !numbered {
+        self.cursor.reset(start);
+        return false;
+      }
+      let _ = self.skip_blanks();
+    }
+    true
+  }
+
+  fn parse_name(&mut self) -> ImportResult<String> {
+    match self.cursor.peek() {
+      Some(c) if c.is_alphabetic() || c == '_' => {
+        Ok(self.cursor.take_while(|c| c.is_alphanumeric() || c == '_' || c == '-' || c == '.'))
+      }
+      _ => self.cursor.error(format!("expected a rule name."))
+    }
+  }
+
+  fn parse_rule(&mut self) -> ImportResult<()> {
+    if !self.skip_production_number() {
+      return self.cursor.error(format!("expected a rule."));
+    }
+    let name = self.parse_name()?;
+    self.skip_blanks()?;
+    self.cursor.expect("::=")?;
+    let expr = self.parse_choice()?;
+    if self.grammar.is_declared(name.as_str()) {
+      return self.cursor.error(format!("the rule `{}` is declared twice.", name));
+    }
+    self.grammar.push_rule(name, expr);
+    Ok(())
+  }
+
+  fn parse_choice(&mut self) -> ImportResult<GExpr> {
+    let mut branches = vec![self.parse_sequence()?];
+    self.skip_blanks()?;
+    while self.cursor.eat("|") {
+      branches.push(self.parse_sequence()?);
+      self.skip_blanks()?;
+    }
+    Ok(if branches.len() == 1 { branches.pop().unwrap() } else { Choice(branches, false) })
+  }
+
+  fn parse_sequence(&mut self) -> ImportResult<GExpr> {
+    let mut elements = vec![self.parse_exception()?];
+    loop {
+      self.skip_blanks()?;
+      match self.cursor.peek() {
+        None | Some('|') | Some(')') => break,
+        _ if self.at_rule_start() => break,
+        _ => elements.push(self.parse_exception()?)
+      }
+    }
+    Ok(if elements.len() == 1 { elements.pop().unwrap() } else { Sequence(elements) })
+  }
+
+  /// `A - B` matches `A` if `B` does not match the same text.
+  fn parse_exception(&mut self) -> ImportResult<GExpr> {
+    let expr = self.parse_postfix()?;
+    self.skip_blanks()?;
+    if self.cursor.eat("-") {
+      let except = self.parse_postfix()?;
+      Ok(Exception(Box::new(expr), Box::new(except)))
+    }
+    else {
+      Ok(expr)
+    }
+  }
+
+  fn parse_postfix(&mut self) -> ImportResult<GExpr> {
+    let mut expr = self.parse_atom()?;
+    loop {
+      expr = match self.cursor.peek() {
+        Some('*') => GExpr::Repeat(Box::new(expr), 0, None),
+        Some('+') => GExpr::Repeat(Box::new(expr), 1, None),
+        Some('?') => GExpr::optional(expr),
+        _ => return Ok(expr)
+      };
+      self.cursor.bump();
+    }
+  }
+
+  fn parse_atom(&mut self) -> ImportResult<GExpr> {
+    self.skip_blanks()?;
+    match self.cursor.peek() {
+      Some('(') => {
+        self.cursor.bump();
+        let expr = self.parse_choice()?;
+        self.cursor.expect(")")?;
+        Ok(expr)
+      }
+      Some(quote) if quote == '"' || quote == '\'' => {
+        self.cursor.bump();
+        let lit = self.cursor.take_while(|c| c != quote);
+        self.cursor.expect(quote.to_string().as_str())?;
+        Ok(Literal(lit))
+      }
+      Some('[') => self.parse_class(),
+      Some('#') => {
+        let c = self.parse_code()?;
+        Ok(Literal(c.to_string()))
+      }
+      Some(_) => Ok(Rule(self.parse_name()?)),
+      None => self.cursor.error(format!("expected an expression but found the end of the file."))
+    }
+  }
+
+  /// `[a-zA-Z]`, `[#x20-#x7E]` and the negated classes `[^abc]`.
+  fn parse_class(&mut self) -> ImportResult<GExpr> {
+    let location = self.cursor.location();
+    self.cursor.expect("[")?;
+    let negated = self.cursor.eat("^");
+    let mut intervals = vec![];
+    while !self.cursor.eat("]") {
+      let lo = self.parse_class_char()?;
+      // A `-` before the closing bracket is a character.
+      let hi = if self.cursor.peek() == Some('-') && self.cursor.peek_nth(1) != Some(']') {
+        self.cursor.bump();
+        self.parse_class_char()?
+      }
+      else { lo };
+      intervals.push((lo, hi));
+    }
+    GExpr::class(intervals, negated, location)
+  }
+
+  fn parse_class_char(&mut self) -> ImportResult<char> {
+    match self.cursor.peek() {
+      Some('#') => self.parse_code(),
+      Some(c) => { self.cursor.bump(); Ok(c) }
+      None => self.cursor.error(format!("unterminated character class."))
+    }
+  }
+
+  /// `#xN` where `N` is the hexadecimal code of the character.
+  fn parse_code(&mut self) -> ImportResult<char> {
+    self.cursor.expect("#x")?;
+    match self.cursor.number(16).and_then(::std::char::from_u32) {
+      Some(c) => Ok(c),
+      None => self.cursor.error(format!("expected a character code after `#x`."))
+    }
+  }
+}
@@ -18,9 +18,11 @@ use rust::BinOpToken as rbtok;
 use rust;
 use std::str::Chars;
 use std::iter::Peekable;
+use std::mem;
 
 use front::ast::*;
 use front::ast::Expression::*;
+use front::import::{self, ImportFormat};
 
 pub struct Parser<'a>
 {
@@ -57,35 +59,82 @@ impl<'a> Parser<'a>
     while self.rp.token != rtok::Eof
     {
       self.parse_inner_attributes()?;
+      // Outer attributes are parsed before we know if they belong to a rule or to a Rust item.
+      let mut attrs = self.rp.parse_outer_attributes()?;
+      if self.is_pub_rule_lhs() {
+        // `pub rule = e` is a shortcut for `#[entry] rule = e`.
+        let span = self.rp.span;
+        self.bump();
+        attrs.push(entry_attribute(span));
+      }
       if self.is_rule_lhs() {
-        self.parse_rule()?;
+        self.parse_rule(attrs)?;
       }
       else {
         match self.rp.parse_item()? {
-          None => self.parse_rule()?,
-          Some(item) => self.grammar.push_rust_item(item),
+          None => self.parse_rule(attrs)?,
+          Some(item) => {
+            let item = attach_outer_attributes(item, attrs);
+            self.grammar.push_rust_item(item)
+          }
         }
       }
     }
     Ok(())
   }
 
-  fn parse_rule(&mut self) -> rust::PResult<'a, ()> {
+  fn parse_rule(&mut self, attrs: Vec<Attribute>) -> rust::PResult<'a, ()> {
     let name = self.parse_rule_decl()?;
+    if self.rp.eat(&rtok::Colon) {
+      let ty = self.rp.parse_ty()?;
+      self.grammar.push_rule_type(name.node, ty);
+    }
     self.rp.expect(&rtok::Eq)?;
     let body = self.parse_rule_rhs(ident_to_string(name.node).as_str())?;
-    self.grammar.push_rule(name, body);
+    self.grammar.push_rule(name, body, attrs);
     Ok(())
   }
 
   fn parse_inner_attributes(&mut self) -> rust::PResult<'a, ()> {
     let inners = self.rp.parse_inner_attributes()?;
     for attr in inners {
-      self.grammar.push_attr(attr);
+      let import = attr.meta().and_then(|meta| {
+        ImportFormat::from_attribute(&*meta.name.as_str())
+          .map(|format| (format, meta.value_str()))
+      });
+      match import {
+        Some((format, Some(path))) => self.import_grammar(format, path.as_str().to_string(), attr.span)?,
+        Some((_, None)) => self.rp.span_err(attr.span,
+          "An import attribute expects the path of the grammar, for example `#![import_pest = \"grammar.pest\"]`."),
+        None => self.grammar.push_attr(attr)
+      }
     }
     Ok(())
   }
 
+  /// The imported grammar is translated into the syntax of Oak and parsed by a sub-parser as if its rules were written in place of the attribute.
+  fn import_grammar(&mut self, format: ImportFormat, path: String, span: Span) -> rust::PResult<'a, ()> {
+    match import::import_file(format, path.as_str()) {
+      Err(msg) => {
+        self.rp.span_err(span, msg.as_str());
+        Ok(())
+      }
+      Ok(imported) => {
+        for warning in &imported.warnings {
+          self.rp.span_warn(span, format!("{}:{}", path, warning).as_str());
+        }
+        let grammar_name = self.grammar.name;
+        let mut parser = Parser {
+          rp: rust::new_parser_from_source_str(self.rp.sess, path, imported.to_oak()),
+          grammar: mem::replace(&mut self.grammar, FGrammar::new(grammar_name))
+        };
+        let res = parser.parse_blocks();
+        self.grammar = parser.grammar;
+        res
+      }
+    }
+  }
+
   fn parse_rule_decl(&mut self) -> rust::PResult<'a, rust::SpannedIdent> {
     let sp = self.rp.span;
     Ok(respan(sp, self.rp.parse_ident()?))
@@ -117,14 +166,15 @@ impl<'a> Parser<'a>
   }
 
   fn parse_semantic_action_or_ty(&mut self, expr: usize, rule_name: &str) -> rust::PResult<'a, usize> {
+    let expr = self.parse_semantic_predicates(expr)?;
     let token = self.rp.token.clone();
     let lo = self.rp.span.lo();
     match token {
       rtok::Gt => {
         self.bump();
-        let ident = self.rp.parse_ident()?;
-        let hi = self.rp.span.hi();
-        Ok(self.alloc_expr(lo, hi, SemanticAction(expr, ident)))
+        let action = self.parse_rust_action()?;
+        let hi = self.rp.prev_span.hi();
+        Ok(self.alloc_expr(lo, hi, SemanticAction(expr, action)))
       },
       rtok::RArrow => {
         self.bump();
@@ -134,7 +184,51 @@ impl<'a> Parser<'a>
     }
   }
 
-  // `()` or `(^)`
+  // `expr &{ pred_1 } ... &{ pred_n }`
+  fn parse_semantic_predicates(&mut self, mut expr: usize) -> rust::PResult<'a, usize> {
+    while self.is_semantic_predicate() {
+      let lo = self.rp.span.lo();
+      self.bump();
+      self.rp.expect(&rtok::OpenDelim(rust::DelimToken::Brace))?;
+      let pred = self.rp.parse_expr()?;
+      self.rp.expect(&rtok::CloseDelim(rust::DelimToken::Brace))?;
+      let hi = self.rp.prev_span.hi();
+      expr = self.alloc_expr(lo, hi, SemanticPredicate(expr, pred));
+    }
+    Ok(expr)
+  }
+
+  fn is_semantic_predicate(&mut self) -> bool {
+    self.rp.token == rtok::BinOp(rbtok::And) &&
+    self.rp.look_ahead(1, |t| match t {
+      &rtok::OpenDelim(rust::DelimToken::Brace) => true,
+      _ => false
+    })
+  }
+
+  // `function`, `Enum::Variant` or `|args| -> Ty { body }`
+  fn parse_rust_action(&mut self) -> rust::PResult<'a, RustAction> {
+    let token = self.rp.token.clone();
+    match token {
+        rtok::BinOp(rbtok::Or)
+      | rtok::OrOr => {
+        let lo = self.rp.span;
+        let closure = self.rp.parse_lambda_expr(lo, rust::CaptureBy::Ref, rust::ThinVec::new())?;
+        Ok(RustAction::Closure(closure))
+      }
+      _ => {
+        let path = self.rp.parse_path(rust::PathStyle::Expr)?;
+        if path.segments.len() == 1 && path.segments[0].parameters.is_none() {
+          Ok(RustAction::Function(path.segments[0].identifier))
+        }
+        else {
+          Ok(RustAction::Path(path))
+        }
+      }
+    }
+  }
+
+  // `()`, `(^)`, `String`, `&str`, `Vec<_>` or `Option<_>`
   fn parse_type(&mut self, lo: BytePos, expr: usize, rule_name: &str) -> rust::PResult<'a, usize> {
     let token = self.rp.token.clone();
     match token {
@@ -154,17 +248,38 @@ impl<'a> Parser<'a>
         Ok(self.alloc_expr(lo, hi, TypeAscription(expr, ty)))
       }
       _ => {
-        let span = self.rp.span;
-        self.rp.span_err(
-          span,
-          format!("In rule {}: Unknown token after `->`. Use the arrow to annotate an expression with the unit type `()` or the invisible type `(^)`.",
-            rule_name).as_str()
-        );
-        Ok(expr)
+        let rty = self.rp.parse_ty()?;
+        let hi = self.rp.prev_span.hi();
+        match self.ascription_type(expr, &rty) {
+          Some(ty) => Ok(self.alloc_expr(lo, hi, TypeAscription(expr, ty))),
+          None => {
+            self.rp.span_err(
+              rty.span,
+              format!("In rule {}: Unsupported type `{}` after `->`. Use the arrow to annotate an expression with the unit type `()`, the invisible type `(^)`, \
+                `String` or `&str` for the text matched, `Vec<_>` on `e*` or `e+` and `Option<_>` on `e?`.",
+                rule_name, rust::ty_to_string(&*rty)).as_str()
+            );
+            Ok(expr)
+          }
+        }
       }
     }
   }
 
+  fn ascription_type(&self, expr: usize, rty: &rust::Ty) -> Option<IType> {
+    let ty: String = rust::ty_to_string(rty).chars()
+      .filter(|c| !c.is_whitespace())
+      .collect();
+    match (ty.as_str(), self.grammar.exprs[expr].clone()) {
+      ("String", _) => Some(IType::Regular(Type::Text)),
+      ("&str", _) => Some(IType::Regular(Type::Slice)),
+      ("Vec<_>", ZeroOrMore(child))
+    | ("Vec<_>", OneOrMore(child)) => Some(IType::Regular(Type::List(child))),
+      ("Option<_>", ZeroOrOne(child)) => Some(IType::Regular(Type::Optional(child))),
+      _ => None
+    }
+  }
+
   fn parse_spanned_expr(&mut self, rule_name: &str) -> rust::PResult<'a, usize> {
     let token = self.rp.token.clone();
     match token {
@@ -205,9 +320,14 @@ impl<'a> Parser<'a>
       rtok::Not => {
         self.parse_prefix(rule_name, |e| NotPredicate(e), "A not predicate (`!expr`)").map(Some)
       }
-      rtok::BinOp(rbtok::And) => {
+      rtok::BinOp(rbtok::And) if !self.is_semantic_predicate() => {
         self.parse_prefix(rule_name, |e| AndPredicate(e), "An and predicate (`&expr`)").map(Some)
       }
+      // `$e` is a shortcut for `e -> &str`.
+      rtok::Dollar => {
+        self.parse_prefix(rule_name, |e| TypeAscription(e, IType::Regular(Type::Slice)),
+          "A text capture (`$expr`)").map(Some)
+      }
       _ => self.parse_rule_suffixed(rule_name)
     }
   }
@@ -281,6 +401,10 @@ impl<'a> Parser<'a>
         self.bump();
         Some(self.last_respan(AnySingleChar))
       },
+      rtok::BinOp(rbtok::Caret) => {
+        self.bump();
+        Some(self.last_respan(Cut))
+      },
       rtok::OpenDelim(rust::DelimToken::Paren) => {
         self.bump();
         let res = self.parse_rule_rhs(rule_name)?;
@@ -410,13 +534,36 @@ impl<'a> Parser<'a>
   }
 
   fn is_rule_lhs(&mut self) -> bool {
-    let token = self.rp.token.clone();
-    if let rtok::Ident(_) = token {
-      !token.is_reserved_ident() &&
-      self.rp.look_ahead(1, |t| match t { &rtok::Eq => true, _ => false})
-    }
-    else {
-      false
-    }
+    self.is_rule_lhs_ahead(0)
+  }
+
+  fn is_pub_rule_lhs(&mut self) -> bool {
+    self.rp.token.is_keyword(rust::keywords::Pub) &&
+    self.is_rule_lhs_ahead(1)
   }
+
+  /// Checks if the tokens at `dist` and `dist+1` are a rule name followed by `=` or by `:` (type annotation).
+  fn is_rule_lhs_ahead(&mut self, dist: usize) -> bool {
+    let is_rule_name = self.rp.look_ahead(dist, |t|
+      match t {
+        &rtok::Ident(_) => !t.is_reserved_ident(),
+        _ => false
+      });
+    is_rule_name &&
+    self.rp.look_ahead(dist + 1, |t| match t { &rtok::Eq | &rtok::Colon => true, _ => false})
+  }
+}
+
+fn attach_outer_attributes(item: RItem, attrs: Vec<Attribute>) -> RItem {
+  item.map(|mut item| {
+    let mut all_attrs = attrs;
+    all_attrs.extend(item.attrs.into_iter());
+    item.attrs = all_attrs;
+    item
+  })
+}
+
+fn entry_attribute(span: Span) -> Attribute {
+  let entry = rust::attr::mk_word_item(rust::Symbol::intern("entry"));
+  rust::attr::mk_attr_outer(span, rust::attr::mk_attr_id(), entry)
 }
@@ -25,6 +25,19 @@ pub fn string_to_ident(cx: &rust::ExtCtxt, name: String) -> Ident {
   cx.ident_of(name.as_str())
 }
 
+/// `sum_expr` becomes `SumExpr`.
+pub fn to_camel_case(name: &str) -> String {
+  name.split('_')
+    .filter(|part| !part.is_empty())
+    .map(|part| {
+      let mut chars = part.chars();
+      chars.next()
+        .map(|first| first.to_uppercase().chain(chars).collect())
+        .unwrap_or(String::new())
+    })
+    .collect()
+}
+
 pub fn cook_lit(name: Name) -> String {
   str_lit(name.to_string().as_str())
 }
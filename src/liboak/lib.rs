@@ -33,6 +33,7 @@ mod middle;
 mod back;
 mod rust;
 mod identifier;
+mod rule_graph;
 
 #[plugin_registrar]
 pub fn plugin_registrar(reg: &mut Registry) {
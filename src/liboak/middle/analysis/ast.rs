@@ -17,6 +17,7 @@ pub use visitor::*;
 pub use front::ast::FExpressionInfo;
 
 use std::default::Default;
+use std::collections::{HashMap, HashSet};
 
 pub type AGrammar<'a, 'b> = Grammar<'a, 'b, FExpressionInfo>;
 
@@ -29,24 +30,135 @@ impl<'a, 'b> AGrammar<'a, 'b>
   pub fn merge_print_typing(&mut self, level: PrintLevel) {
     self.attributes.print_typing = self.attributes.print_typing.merge(level);
   }
+
+  pub fn alloc_expr(&mut self, span: Span, expr: Expression) -> usize {
+    let expr_idx = self.exprs.len();
+    self.exprs.push(expr);
+    self.exprs_info.push(FExpressionInfo { span: span });
+    expr_idx
+  }
 }
 
 pub struct GrammarAttributes
 {
   pub print_code: PrintLevel,
-  pub print_typing: PrintLevel
-
+  pub print_typing: PrintLevel,
+  /// Rule called after each terminal of the rules not annotated with `#[no_skip]`.
+  pub skip_rule: Option<SpannedIdent>,
+  /// Generate a Rust type for the rules without semantic actions (`#![generate_ast]`).
+  pub generate_ast: bool,
+  /// Generate the functions `print_<rule>` and `write_<rule>` (`#![generate_printer]`).
+  pub generate_printer: bool,
+  /// Generate the functions `generate_<rule>` producing random inputs (`#![generator]`).
+  pub generator: bool,
+  /// Files in which the grammar is exported (`#![export_<format> = "path"]`).
+  pub exports: Vec<Export>,
+  /// Maximal size of the rules inlined at their call sites (`#![inline_threshold = 8]`), `0` (the default) disables the inlining.
+  pub inline_threshold: usize,
+  pub rules: HashMap<Ident, RuleAttributes>
 }
 
 impl Default for GrammarAttributes {
   fn default() -> Self {
     GrammarAttributes {
       print_code: PrintLevel::default(),
-      print_typing: PrintLevel::default()
+      print_typing: PrintLevel::default(),
+      skip_rule: None,
+      generate_ast: false,
+      generate_printer: false,
+      generator: false,
+      exports: vec![],
+      inline_threshold: 0,
+      rules: HashMap::new()
+    }
+  }
+}
+
+#[derive(Clone, Default)]
+pub struct RuleAttributes
+{
+  pub no_skip: bool,
+  /// Replaces the expectations of the rule when it fails without consuming input.
+  pub label: Option<String>,
+  /// Entry rules are declared with `pub` or `#[entry]`, they are the only public rules of the grammar.
+  pub entry: bool,
+  /// Rules unreachable from the entry rules are not compiled.
+  pub unreachable: bool,
+  /// Doc comments and Rust attributes (such as `#[cfg]` or `#[cold]`) forwarded to the generated functions.
+  pub rust_attributes: Vec<Attribute>,
+  /// Type declared with `rule: Type = e`, checked against the inferred type.
+  pub declared_type: Option<RTy>,
+  /// Rust type generated for the rule when `#![generate_ast]` is set.
+  pub ast_type: Option<AstType>,
+  /// Rules whose generated type contains the type of this rule, the fields calling them are boxed.
+  pub boxed_calls: HashSet<Ident>,
+  /// Text printed for the calls to this rule by the generated printers.
+  pub print_separator: Option<String>,
+  /// TextMate scope of the tokens matched by the rule (`#[scope = "keyword.control"]`).
+  pub scope: Option<String>
+}
+
+#[derive(Clone)]
+pub enum AstType
+{
+  /// The value of the rule is a struct built by the function `constructor`.
+  Struct {
+    name: Ident,
+    constructor: Ident
+  },
+  /// The rule is a choice and each branch is a variant built by its own constructor.
+  Enum {
+    name: Ident,
+    variants: Vec<(Ident, Ident)>
+  }
+}
+
+impl AstType
+{
+  pub fn name(&self) -> Ident {
+    match self {
+      &AstType::Struct { name, .. } => name,
+      &AstType::Enum { name, .. } => name
     }
   }
 }
 
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ExportFormat
+{
+  Ebnf,
+  W3cEbnf,
+  Railroad,
+  /// Rule call graph annotated with the inferred types, exported after the typing.
+  Dot,
+  TreeSitter,
+  TextMate
+}
+
+impl ExportFormat
+{
+  pub fn from_attribute(name: &str) -> Option<ExportFormat> {
+    use self::ExportFormat::*;
+    match name {
+      "export_ebnf" => Some(Ebnf),
+      "export_w3c_ebnf" => Some(W3cEbnf),
+      "export_railroad" => Some(Railroad),
+      "export_dot" => Some(Dot),
+      "export_tree_sitter" => Some(TreeSitter),
+      "export_textmate" => Some(TextMate),
+      _ => None
+    }
+  }
+}
+
+#[derive(Clone)]
+pub struct Export
+{
+  pub format: ExportFormat,
+  pub path: String,
+  pub span: Span
+}
+
 #[derive(Clone, Copy, PartialEq, Eq)]
 pub enum PrintLevel
 {
@@ -14,12 +14,27 @@
 
 use middle::analysis::ast::*;
 
-use rust::{MetaItemKind, MetaItem};
+use rust::{MetaItemKind, MetaItem, LitKind, respan, lev_distance};
+
+use std::collections::HashMap;
 
 pub fn decorate_with_attributes<'a, 'b>(mut grammar: AGrammar<'a, 'b>,
-  attributes: Vec<Attribute>) -> Partial<AGrammar<'a, 'b>>
+  attributes: Vec<Attribute>, rules_attributes: HashMap<Ident, Vec<Attribute>>)
+  -> Partial<AGrammar<'a, 'b>>
 {
   merge_grammar_attributes(&mut grammar, attributes);
+  merge_rules_attributes(&mut grammar, rules_attributes);
+  Partial::Value(grammar)
+}
+
+pub fn decorate_with_declared_types<'a, 'b>(mut grammar: AGrammar<'a, 'b>,
+  rules_types: HashMap<Ident, RTy>) -> Partial<AGrammar<'a, 'b>>
+{
+  for (rule, ty) in rules_types {
+    grammar.attributes.rules.entry(rule)
+      .or_insert_with(RuleAttributes::default)
+      .declared_type = Some(ty);
+  }
   Partial::Value(grammar)
 }
 
@@ -44,6 +59,47 @@ fn merge_grammar_attr<'a, 'b>(grammar: &mut AGrammar<'a, 'b>, meta_item: MetaIte
     },
     &MetaItemKind::Word if meta_item.name == "show_typing" => {
       grammar.merge_print_typing(PrintLevel::Show);
+    },
+    &MetaItemKind::Word if meta_item.name == "generate_ast" => {
+      grammar.attributes.generate_ast = true;
+    },
+    &MetaItemKind::Word if meta_item.name == "generate_printer" => {
+      grammar.attributes.generate_printer = true;
+    },
+    &MetaItemKind::Word if meta_item.name == "generator" => {
+      grammar.attributes.generator = true;
+    },
+    &MetaItemKind::NameValue(_) if meta_item.name == "skip" => {
+      match meta_item.value_str() {
+        Some(rule) => {
+          let rule = string_to_ident(grammar.cx, rule.to_string());
+          grammar.attributes.skip_rule = Some(respan(meta_item.span, rule));
+        }
+        None => grammar.span_err(meta_item.span, format!(
+          "The attribute `skip` expects the name of a rule as a string, for example `#![skip = \"spacing\"]`."))
+      }
+    },
+    &MetaItemKind::NameValue(_) if ExportFormat::from_attribute(&*meta_item.name.as_str()).is_some() => {
+      let format = ExportFormat::from_attribute(&*meta_item.name.as_str()).unwrap();
+      match meta_item.value_str() {
+        Some(path) => {
+          grammar.attributes.exports.push(Export {
+            format: format,
+            path: path.to_string(),
+            span: meta_item.span
+          });
+        }
+        None => grammar.span_err(meta_item.span, format!(
+          "The attribute `{}` expects the path of the exported file as a string, for example `#![{} = \"grammar.txt\"]`.",
+          meta_item.name, meta_item.name))
+      }
+    },
+    &MetaItemKind::NameValue(ref lit) if meta_item.name == "inline_threshold" => {
+      match lit.node {
+        LitKind::Int(threshold, _) => grammar.attributes.inline_threshold = threshold as usize,
+        _ => grammar.span_err(meta_item.span, format!(
+          "The attribute `inline_threshold` expects an integer, for example `#![inline_threshold = 8]`."))
+      }
     },
       &MetaItemKind::Word
     | &MetaItemKind::List(_)
@@ -54,3 +110,93 @@ fn merge_grammar_attr<'a, 'b>(grammar: &mut AGrammar<'a, 'b>, meta_item: MetaIte
     }
   }
 }
+
+/// Attributes of the rules handled by Oak.
+const OAK_RULE_ATTRIBUTES: &'static [&'static str] = &["no_skip", "entry", "label", "print_separator", "scope"];
+
+/// Rust attributes forwarded to the generated functions of the rules.
+const RUST_RULE_ATTRIBUTES: &'static [&'static str] = &["doc", "allow", "warn", "deny", "forbid",
+  "deprecated", "must_use", "cfg", "cfg_attr", "cold", "inline"];
+
+fn merge_rules_attributes<'a, 'b>(grammar: &mut AGrammar<'a, 'b>,
+  rules_attrs: HashMap<Ident, Vec<Attribute>>)
+{
+  for (rule, attrs) in rules_attrs {
+    let mut rule_attrs = RuleAttributes::default();
+    for attr in attrs {
+      match attr.meta() {
+        Some(meta_item) => {
+          let name = meta_item.name.to_string();
+          if OAK_RULE_ATTRIBUTES.contains(&name.as_str()) {
+            merge_rule_attr(grammar, &mut rule_attrs, meta_item);
+          }
+          // Doc comments are `doc` attributes.
+          else if RUST_RULE_ATTRIBUTES.contains(&name.as_str()) {
+            rule_attrs.rust_attributes.push(attr);
+          }
+          else {
+            warn_unknown_rule_attr(grammar, &meta_item);
+          }
+        }
+        None => rule_attrs.rust_attributes.push(attr)
+      }
+    }
+    grammar.attributes.rules.insert(rule, rule_attrs);
+  }
+}
+
+/// Unknown attributes are not forwarded to rustc, they are reported and ignored. A misspelled Oak attribute, such as `#[no_skp]`, is reported with the closest Oak attribute.
+fn warn_unknown_rule_attr<'a, 'b>(grammar: &AGrammar<'a, 'b>, meta_item: &MetaItem) {
+  let name = meta_item.name.to_string();
+  let closest = OAK_RULE_ATTRIBUTES.iter()
+    .map(|oak_attr| (lev_distance(name.as_str(), oak_attr), oak_attr))
+    .filter(|&(distance, _)| distance <= 2)
+    .min();
+  let msg = match closest {
+    Some((_, oak_attr)) => format!(
+      "Unknown attribute `{}`, did you mean `{}`? It will be ignored.", name, oak_attr),
+    None => format!("Unknown attribute `{}`: it will be ignored.", name)
+  };
+  grammar.cx.span_warn(meta_item.span, msg.as_str());
+}
+
+/// The name of `meta_item` is in `OAK_RULE_ATTRIBUTES`.
+fn merge_rule_attr<'a, 'b>(grammar: &AGrammar<'a, 'b>,
+  rule_attrs: &mut RuleAttributes, meta_item: MetaItem)
+{
+  match &meta_item.node {
+    &MetaItemKind::Word if meta_item.name == "no_skip" => {
+      rule_attrs.no_skip = true;
+    },
+    &MetaItemKind::Word if meta_item.name == "entry" => {
+      rule_attrs.entry = true;
+    },
+    _ if meta_item.name == "no_skip" || meta_item.name == "entry" => {
+      grammar.span_err(meta_item.span, format!(
+        "The attribute `{}` does not take arguments, for example `#[{}]`.",
+        meta_item.name, meta_item.name))
+    },
+    _ if meta_item.name == "label" => {
+      match meta_item.value_str() {
+        Some(label) => rule_attrs.label = Some(label.to_string()),
+        None => grammar.span_err(meta_item.span, format!(
+          "The attribute `label` expects a string, for example `#[label = \"expression\"]`."))
+      }
+    },
+    _ if meta_item.name == "print_separator" => {
+      match meta_item.value_str() {
+        Some(separator) => rule_attrs.print_separator = Some(separator.to_string()),
+        None => grammar.span_err(meta_item.span, format!(
+          "The attribute `print_separator` expects a string, for example `#[print_separator = \" \"]`."))
+      }
+    },
+    _ if meta_item.name == "scope" => {
+      match meta_item.value_str() {
+        Some(scope) => rule_attrs.scope = Some(scope.to_string()),
+        None => grammar.span_err(meta_item.span, format!(
+          "The attribute `scope` expects a TextMate scope, for example `#[scope = \"keyword.control\"]`."))
+      }
+    },
+    _ => unreachable!("Unknown Oak attribute `{}`.", meta_item.name)
+  }
+}
@@ -0,0 +1,96 @@
+// Copyright 2017 Pierre Talbot (IRCAM)
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! A cut `^` commits to the choice branch in which it appears. To keep its meaning obvious, it must be an element of a sequence forming a branch of a choice, such as in `"if" ^ cond block / other`.
+
+use middle::analysis::ast::*;
+use partial::Partial::*;
+use std::collections::HashSet;
+
+pub struct CutPosition<'a: 'c, 'b: 'a, 'c>
+{
+  grammar: &'c AGrammar<'a, 'b>,
+  allowed_cuts: HashSet<usize>,
+  misplaced_cut: bool
+}
+
+impl<'a, 'b, 'c> CutPosition<'a, 'b, 'c>
+{
+  pub fn analyse(grammar: AGrammar<'a, 'b>) -> Partial<AGrammar<'a, 'b>> {
+    if CutPosition::has_misplaced_cut(&grammar) {
+      Nothing
+    } else {
+      Value(grammar)
+    }
+  }
+
+  fn has_misplaced_cut(grammar: &'c AGrammar<'a, 'b>) -> bool {
+    let mut analyser = CutPosition {
+      grammar: grammar,
+      allowed_cuts: HashSet::new(),
+      misplaced_cut: false
+    };
+    for rule in &grammar.rules {
+      analyser.visit_expr(rule.expr_idx);
+    }
+    analyser.misplaced_cut
+  }
+
+  /// Skips the expressions that do not change the structure of a branch.
+  fn branch_body(&self, mut branch: usize) -> usize {
+    loop {
+      match self.grammar.expr_by_index(branch) {
+          Expression::SemanticAction(child, _)
+        | Expression::SemanticPredicate(child, _)
+        | Expression::TypeAscription(child, _)
+        | Expression::SpannedExpr(child) => branch = child,
+        _ => return branch
+      }
+    }
+  }
+}
+
+impl<'a, 'b, 'c> ExprByIndex for CutPosition<'a, 'b, 'c>
+{
+  fn expr_by_index(&self, index: usize) -> Expression {
+    self.grammar.expr_by_index(index).clone()
+  }
+}
+
+impl<'a, 'b, 'c> Visitor<()> for CutPosition<'a, 'b, 'c>
+{
+  unit_visitor_impl!(str_literal);
+  unit_visitor_impl!(atom);
+  unit_visitor_impl!(non_terminal);
+  unit_visitor_impl!(sequence);
+
+  fn visit_choice(&mut self, _this: usize, children: Vec<usize>) {
+    for &branch in &children {
+      let body = self.branch_body(branch);
+      if let Expression::Sequence(seq) = self.grammar.expr_by_index(body) {
+        self.allowed_cuts.extend(seq.into_iter());
+      }
+    }
+    walk_exprs(self, children);
+  }
+
+  fn visit_cut(&mut self, this: usize) {
+    if !self.allowed_cuts.contains(&this) {
+      self.grammar.expr_err(this,
+        format!("A cut `^` must be an element of a sequence directly inside a branch of a choice, \
+          such as in `\"if\" ^ cond block / other`."));
+      self.misplaced_cut = true;
+    }
+  }
+}
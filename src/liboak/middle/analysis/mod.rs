@@ -19,22 +19,38 @@ use middle::analysis::undeclared_rule::*;
 use middle::analysis::undeclared_action::*;
 use middle::analysis::well_formedness::*;
 use middle::analysis::attribute::*;
+use middle::analysis::skip::*;
+use middle::analysis::cut::*;
+use middle::analysis::reachability::*;
+use middle::analysis::shadowing::*;
 
 mod duplicate;
 mod undeclared_rule;
 mod undeclared_action;
 mod well_formedness;
 mod attribute;
+mod skip;
+mod cut;
+mod reachability;
+mod shadowing;
 pub mod ast;
 
 pub fn analyse<'a, 'b>(cx: &'a ExtCtxt<'b>, fgrammar: FGrammar) -> Partial<AGrammar<'a, 'b>> {
   let grammar = AGrammar::new(cx, fgrammar.name, fgrammar.exprs, fgrammar.exprs_info);
   let frust_items = fgrammar.rust_items;
   let fattributes = fgrammar.attributes;
+  let frules_attributes = fgrammar.rules_attributes;
+  let frules_types = fgrammar.rules_types;
   rule_duplicate(grammar, fgrammar.rules)
   .and_then(|grammar| rust_functions_duplicate(grammar, frust_items))
   .and_then(|grammar| UndeclaredRule::analyse(grammar))
   .and_then(|grammar| UndeclaredAction::analyse(grammar))
+  .and_then(|grammar| CutPosition::analyse(grammar))
   .and_then(|grammar| WellFormedness::analyse(grammar))
-  .and_then(|grammar| decorate_with_attributes(grammar, fattributes))
+  .and_then(|grammar| decorate_with_attributes(grammar, fattributes, frules_attributes))
+  .and_then(|grammar| decorate_with_declared_types(grammar, frules_types))
+  .and_then(|grammar| Skip::analyse(grammar))
+  .and_then(|grammar| unreachable_rules(grammar))
+  .and_then(|grammar| unused_functions(grammar))
+  .and_then(|grammar| ShadowedBranch::analyse(grammar))
 }
@@ -0,0 +1,189 @@
+// Copyright 2017 Pierre Talbot (IRCAM)
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! If the grammar declares entry rules (with `pub` or `#[entry]`), the rules unreachable from them are reported and will not be compiled.
+//!
+//! The private functions declared in the grammar are reported if they are not used by the reachable rules. A function is used if it is the action of a reachable rule, or if its name appears in a closure action, in a semantic predicate, in another item of the grammar or in the body of a used function. The public functions are used and their bodies are followed as well.
+
+use middle::analysis::ast::*;
+use rule_graph::*;
+use partial::Partial::*;
+use rust;
+use std::collections::{HashMap, HashSet};
+
+pub fn unreachable_rules<'a, 'b>(mut grammar: AGrammar<'a, 'b>) -> Partial<AGrammar<'a, 'b>> {
+  if !grammar.has_entry_rules() {
+    return Value(grammar);
+  }
+  let entries: Vec<Ident> = grammar.rules.iter()
+    .map(|rule| rule.ident())
+    .filter(|rule| grammar.rule_attributes(*rule).entry)
+    .collect();
+  let reachable = RuleGraph::new(&grammar).reachable_from(entries);
+  for rule in grammar.rules.clone() {
+    if !reachable.contains(&rule.ident()) {
+      grammar.cx.span_warn(rule.span(), format!(
+        "Rule `{}` is unreachable from the entry rules: no code will be generated for it.",
+        rule.ident()).as_str());
+      grammar.attributes.rules.entry(rule.ident())
+        .or_insert_with(RuleAttributes::default)
+        .unreachable = true;
+    }
+  }
+  Value(grammar)
+}
+
+pub fn unused_functions<'a, 'b>(grammar: AGrammar<'a, 'b>) -> Partial<AGrammar<'a, 'b>> {
+  let mut uses = FunctionUses {
+    grammar: &grammar,
+    used: HashSet::new(),
+    code: grammar.rust_items.iter().map(|item| rust::item_to_string(item)).collect()
+  };
+  for (name, function) in &grammar.rust_functions {
+    if let rust::Visibility::Public = function.vis {
+      uses.use_function(*name);
+    }
+  }
+  for rule in &grammar.rules {
+    if grammar.is_reachable_rule(rule.ident()) {
+      uses.visit_expr(rule.expr_idx);
+    }
+  }
+  let functions: HashMap<String, String> = grammar.rust_functions.iter()
+    .map(|(name, function)| (name.to_string(), rust::item_to_string(function)))
+    .collect();
+  let used = used_functions(&functions, uses.used, uses.code);
+  for (name, function) in &grammar.rust_functions {
+    if !used.contains(&name.to_string()) {
+      grammar.cx.span_warn(function.span, format!(
+        "Function `{}` is not used by the actions of the reachable rules.", name).as_str());
+    }
+  }
+  Value(grammar)
+}
+
+/// Extends `used` with the functions whose name appears as a word in `code`, and then in the bodies of the functions found, until a fixpoint is reached. `functions` maps the name of each function to its code.
+fn used_functions(functions: &HashMap<String, String>, mut used: HashSet<String>,
+  mut code: Vec<String>) -> HashSet<String>
+{
+  while let Some(code) = code.pop() {
+    let words: HashSet<&str> = code
+      .split(|c: char| !(c.is_alphanumeric() || c == '_'))
+      .collect();
+    for (name, body) in functions {
+      if !used.contains(name) && words.contains(name.as_str()) {
+        used.insert(name.clone());
+        code.push(body.clone());
+      }
+    }
+  }
+  used
+}
+
+struct FunctionUses<'a: 'c, 'b: 'a, 'c>
+{
+  grammar: &'c AGrammar<'a, 'b>,
+  used: HashSet<String>,
+  /// Rust code in which the names of the functions are searched.
+  code: Vec<String>
+}
+
+impl<'a, 'b, 'c> FunctionUses<'a, 'b, 'c>
+{
+  /// Marks the function as used and follows its body.
+  fn use_function(&mut self, ident: Ident) {
+    if self.used.insert(ident.to_string()) {
+      if let Some(function) = self.grammar.rust_functions.get(&ident) {
+        self.code.push(rust::item_to_string(function));
+      }
+    }
+  }
+}
+
+impl<'a, 'b, 'c> ExprByIndex for FunctionUses<'a, 'b, 'c>
+{
+  fn expr_by_index(&self, index: usize) -> Expression {
+    self.grammar.expr_by_index(index)
+  }
+}
+
+impl<'a, 'b, 'c> Visitor<()> for FunctionUses<'a, 'b, 'c>
+{
+  unit_visitor_impl!(str_literal);
+  unit_visitor_impl!(atom);
+  unit_visitor_impl!(sequence);
+  unit_visitor_impl!(choice);
+  unit_visitor_impl!(non_terminal);
+
+  fn visit_semantic_action(&mut self, _this: usize, child: usize, action: RustAction) {
+    match action {
+      RustAction::Function(ident) => self.use_function(ident),
+      RustAction::Closure(closure) => self.code.push(rust::expr_to_string(&closure)),
+      RustAction::Path(_)
+    | RustAction::Constructor(..) => ()
+    }
+    self.visit_expr(child)
+  }
+
+  fn visit_semantic_predicate(&mut self, _this: usize, child: usize, pred: RExpr) {
+    self.code.push(rust::expr_to_string(&pred));
+    self.visit_expr(child)
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::used_functions;
+  use std::collections::HashMap;
+
+  fn unused(functions: &[(&str, &str)], used: &[&str], code: &[&str]) -> Vec<String> {
+    let functions: HashMap<String, String> = functions.iter()
+      .map(|&(name, body)| (name.to_string(), body.to_string()))
+      .collect();
+    let used = used_functions(&functions,
+      used.iter().map(|name| name.to_string()).collect(),
+      code.iter().map(|code| code.to_string()).collect());
+    let mut unused: Vec<String> = functions.keys()
+      .filter(|name| !used.contains(*name))
+      .cloned()
+      .collect();
+    unused.sort();
+    unused
+  }
+
+  // Functions of the grammar `tests/grammars/unused.rs`: `to_decimal` is the action of a reachable rule and `negate` is called by a closure action.
+  const UNUSED_GRAMMAR: &'static [(&'static str, &'static str)] = &[
+    ("to_decimal", "fn to_decimal(digits: Vec<char>) -> u32 { digits_value(digits, 10) }"),
+    ("from_hex", "fn from_hex(digits: Vec<char>) -> u32 { digits_value(digits, 16) }"),
+    ("digits_value", "fn digits_value(digits: Vec<char>, radix: u32) -> u32 { 0 }"),
+    ("make_number", "fn make_number(n: u32) -> i64 { n as i64 }"),
+    ("negate", "fn negate(n: u32) -> i64 { -(n as i64) }")
+  ];
+
+  #[test]
+  fn test_body_of_actions() {
+    assert_eq!(
+      unused(UNUSED_GRAMMAR,
+        &["to_decimal", "make_number"],
+        &["fn to_decimal(digits: Vec<char>) -> u32 { digits_value(digits, 10) }",
+          "|n: u32| -> i64 { negate(n) }"]),
+      vec![format!("from_hex")]);
+  }
+
+  #[test]
+  fn test_word_boundaries() {
+    let functions = &[("value", "fn value() {}"), ("values", "fn values() { value_of() }")];
+    assert_eq!(unused(functions, &[], &["values()"]), vec![format!("value")]);
+  }
+}
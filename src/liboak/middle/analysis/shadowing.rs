@@ -0,0 +1,285 @@
+// Copyright 2017 Pierre Talbot (IRCAM)
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! A branch of a choice is shadowed when every input it accepts is already accepted by a previous branch, for example `"a" / "ab"` or `identifier / keyword` when every keyword is an identifier. Since the choice is ordered, such a branch is never tried successfully and it is reported with a warning.
+//!
+//! The analysis abstracts an expression by *patterns*, sequences of character sets describing the beginning of the input. For the previous branches, we compute the patterns that guarantee the success of the branch (`e` succeeds if the input starts with one of them), and for the analysed branch, the patterns the input must start with for the branch to succeed. The branch is shadowed if each of its necessary patterns is covered by a sufficient pattern of a previous branch. Literals, character classes, sequences of them, repetitions and the calls to non-recursive rules are abstracted, the other expressions give up on the analysis, so no warning is reported when we cannot be sure.
+
+use middle::analysis::ast::*;
+use partial::Partial::*;
+
+type CharSet = Vec<(char, char)>;
+type Pattern = Vec<CharSet>;
+
+/// Maximal number of patterns of an expression, beyond it the analysis gives up.
+const MAX_PATTERNS: usize = 32;
+/// Maximal depth of the rule calls followed by the analysis.
+const MAX_DEPTH: usize = 8;
+
+pub struct ShadowedBranch<'a: 'c, 'b: 'a, 'c>
+{
+  grammar: &'c AGrammar<'a, 'b>,
+  visiting: Vec<Ident>
+}
+
+impl<'a, 'b, 'c> ShadowedBranch<'a, 'b, 'c>
+{
+  pub fn analyse(grammar: AGrammar<'a, 'b>) -> Partial<AGrammar<'a, 'b>> {
+    ShadowedBranch::warn_shadowed_branches(&grammar);
+    Value(grammar)
+  }
+
+  fn warn_shadowed_branches(grammar: &'c AGrammar<'a, 'b>) {
+    let mut analyser = ShadowedBranch {
+      grammar: grammar,
+      visiting: vec![]
+    };
+    for rule in &grammar.rules {
+      analyser.visit_expr(rule.expr_idx);
+    }
+  }
+
+  /// Indexes of the previous branches shadowing `branch`, or `None` if it is not shadowed.
+  fn shadowing_branches(&mut self, previous: &[usize], branch: usize) -> Option<Vec<usize>> {
+    let necessary = self.necessary(branch);
+    if necessary.is_empty() {
+      return None;
+    }
+    let sufficient: Vec<_> = previous.iter()
+      .map(|&prev| self.sufficient(prev))
+      .collect();
+    let mut shadowing = vec![];
+    for pattern in necessary {
+      let covering = sufficient.iter()
+        .position(|patterns| patterns.iter().any(|p| covers(p, &pattern)));
+      match covering {
+        None => return None,
+        Some(i) => {
+          if !shadowing.contains(&previous[i]) {
+            shadowing.push(previous[i]);
+          }
+        }
+      }
+    }
+    shadowing.sort();
+    Some(shadowing)
+  }
+
+  /// Calls `f` on the body of `rule` unless the rule is already being visited (or the calls are too deep), in which case `default` is returned.
+  fn rule_body<R, F>(&mut self, rule: Ident, default: R, f: F) -> R where
+   F: FnOnce(&mut Self, usize) -> R
+  {
+    if self.visiting.contains(&rule) || self.visiting.len() >= MAX_DEPTH {
+      return default;
+    }
+    self.visiting.push(rule);
+    let expr_idx = self.grammar.expr_index_of_rule(rule);
+    let res = f(self, expr_idx);
+    self.visiting.pop();
+    res
+  }
+
+  /// The pattern matched by `expr` if it succeeds exactly when the input starts with it, in which case it consumes its length.
+  fn exact(&mut self, expr: usize) -> Option<Pattern> {
+    use ast::Expression::*;
+    match self.grammar.expr_by_index(expr) {
+      StrLiteral(lit) => Some(lit.chars().map(|c| vec![(c, c)]).collect()),
+      AnySingleChar => Some(vec![vec![('\0', ::std::char::MAX)]]),
+      CharacterClass(class) => Some(vec![class.intervals.iter().map(|i| (i.lo, i.hi)).collect()]),
+      NonTerminalSymbol(rule) => self.rule_body(rule, None, |this, body| this.exact(body)),
+      Sequence(children) => {
+        let mut pattern = vec![];
+        for child in children {
+          match self.exact(child) {
+            Some(child) => pattern.extend(child.into_iter()),
+            None => return None
+          }
+        }
+        Some(pattern)
+      }
+      Choice(children) => {
+        // A choice of single characters is a character set.
+        let mut set = vec![];
+        for child in children {
+          match self.exact(child) {
+            Some(mut pattern) if pattern.len() == 1 => set.extend(pattern.pop().unwrap().into_iter()),
+            _ => return None
+          }
+        }
+        Some(vec![set])
+      }
+        SemanticAction(child, _)
+      | TypeAscription(child, _)
+      | SpannedExpr(child) => self.exact(child),
+      _ => None
+    }
+  }
+
+  /// The patterns guaranteeing the success of `expr`: it succeeds if the input starts with one of them. The empty pattern means that `expr` always succeeds.
+  fn sufficient(&mut self, expr: usize) -> Vec<Pattern> {
+    use ast::Expression::*;
+    if let Some(pattern) = self.exact(expr) {
+      return vec![pattern];
+    }
+    match self.grammar.expr_by_index(expr) {
+      NonTerminalSymbol(rule) => self.rule_body(rule, vec![], |this, body| this.sufficient(body)),
+      Sequence(children) => {
+        let mut prefix = vec![];
+        let mut children = children.into_iter();
+        while let Some(child) = children.next() {
+          match self.exact(child) {
+            Some(pattern) => prefix.extend(pattern.into_iter()),
+            None => {
+              let patterns = concat(&vec![prefix], self.sufficient(child));
+              // The remaining elements start at an unknown position, they must always succeed.
+              let rest_succeeds = children.all(|child| self.always_succeeds(child));
+              return if rest_succeeds { patterns } else { vec![] };
+            }
+          }
+        }
+        vec![prefix]
+      }
+      Choice(children) => {
+        let mut patterns = vec![];
+        for child in children {
+          patterns.extend(self.sufficient(child).into_iter());
+        }
+        if patterns.len() > MAX_PATTERNS { vec![] } else { patterns }
+      }
+      ZeroOrMore(_)
+    | ZeroOrOne(_)
+    | Cut => vec![vec![]],
+      OneOrMore(child)
+    | SemanticAction(child, _)
+    | TypeAscription(child, _)
+    | SpannedExpr(child) => self.sufficient(child),
+      _ => vec![]
+    }
+  }
+
+  fn always_succeeds(&mut self, expr: usize) -> bool {
+    self.sufficient(expr).iter().any(|pattern| pattern.is_empty())
+  }
+
+  /// The patterns that the input must start with for `expr` to succeed. The empty pattern means that nothing is known on the input.
+  fn necessary(&mut self, expr: usize) -> Vec<Pattern> {
+    use ast::Expression::*;
+    if let Some(pattern) = self.exact(expr) {
+      return vec![pattern];
+    }
+    match self.grammar.expr_by_index(expr) {
+      NonTerminalSymbol(rule) => self.rule_body(rule, vec![vec![]], |this, body| this.necessary(body)),
+      Sequence(children) => {
+        let mut prefix = vec![];
+        for child in children {
+          match self.grammar.expr_by_index(child) {
+            // Predicates do not consume input, the next element starts at the same position.
+            NotPredicate(_) | AndPredicate(_) | Cut => continue,
+            _ => ()
+          }
+          match self.exact(child) {
+            Some(pattern) => prefix.extend(pattern.into_iter()),
+            None => return concat(&vec![prefix], self.necessary(child))
+          }
+        }
+        vec![prefix]
+      }
+      Choice(children) => {
+        let mut patterns = vec![];
+        for child in children {
+          patterns.extend(self.necessary(child).into_iter());
+        }
+        if patterns.len() > MAX_PATTERNS { vec![vec![]] } else { patterns }
+      }
+      OneOrMore(child)
+    | SemanticAction(child, _)
+    | SemanticPredicate(child, _)
+    | TypeAscription(child, _)
+    | SpannedExpr(child) => self.necessary(child),
+      _ => vec![vec![]]
+    }
+  }
+}
+
+/// Every pattern of `prefixes` followed by every pattern of `suffixes`.
+fn concat(prefixes: &Vec<Pattern>, suffixes: Vec<Pattern>) -> Vec<Pattern> {
+  let mut patterns = vec![];
+  for prefix in prefixes {
+    for suffix in &suffixes {
+      let mut pattern = prefix.clone();
+      pattern.extend(suffix.iter().cloned());
+      patterns.push(pattern);
+    }
+  }
+  if patterns.len() > MAX_PATTERNS {
+    prefixes.clone()
+  }
+  else {
+    patterns
+  }
+}
+
+/// `sufficient` covers `necessary` if every input starting with `necessary` starts with `sufficient`.
+fn covers(sufficient: &Pattern, necessary: &Pattern) -> bool {
+  sufficient.len() <= necessary.len() &&
+  sufficient.iter().zip(necessary.iter())
+    .all(|(sup, sub)| is_subset(sub, sup))
+}
+
+fn is_subset(sub: &CharSet, sup: &CharSet) -> bool {
+  sub.iter().all(|&(lo, hi)| {
+    let mut from = lo as u32;
+    // The intervals of `sup` are not sorted, we extend the covered range until we reach `hi`.
+    loop {
+      let next = sup.iter()
+        .filter(|&&(l, h)| l as u32 <= from && from <= h as u32)
+        .map(|&(_, h)| h as u32)
+        .max();
+      match next {
+        None => return false,
+        Some(h) if h >= hi as u32 => return true,
+        Some(h) => from = h + 1
+      }
+    }
+  })
+}
+
+impl<'a, 'b, 'c> ExprByIndex for ShadowedBranch<'a, 'b, 'c>
+{
+  fn expr_by_index(&self, index: usize) -> Expression {
+    self.grammar.expr_by_index(index).clone()
+  }
+}
+
+impl<'a, 'b, 'c> Visitor<()> for ShadowedBranch<'a, 'b, 'c>
+{
+  unit_visitor_impl!(str_literal);
+  unit_visitor_impl!(atom);
+  unit_visitor_impl!(non_terminal);
+  unit_visitor_impl!(sequence);
+
+  fn visit_choice(&mut self, _this: usize, children: Vec<usize>) {
+    for (i, &branch) in children.iter().enumerate().skip(1) {
+      if let Some(shadowing) = self.shadowing_branches(&children[..i], branch) {
+        let mut warnings = vec![(self.grammar[branch].span(), format!(
+          "This branch is unreachable: every input it accepts is already accepted by a previous branch of the choice."))];
+        for prev in shadowing {
+          warnings.push((self.grammar[prev].span(), format!("The branch is shadowed by this previous branch.")));
+        }
+        self.grammar.multi_locations_warn(warnings);
+      }
+    }
+    walk_exprs(self, children);
+  }
+}
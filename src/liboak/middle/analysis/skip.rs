@@ -0,0 +1,114 @@
+// Copyright 2017 Pierre Talbot (IRCAM)
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Automatic insertion of the skip rule declared with `#![skip = "rule"]`.
+//!
+//! The skip rule is called after every terminal (string literal, `.` and character class) of the rules, and after every call to a lexical rule. Lexical rules are the rules annotated with `#[no_skip]`, the skip rule itself, and every rule reachable from one of them; their bodies are left untouched. Expressions under syntactic predicates are never rewritten since they do not consume input.
+
+use middle::analysis::ast::*;
+use rule_graph::*;
+use partial::Partial::*;
+use std::collections::HashSet;
+
+pub struct Skip<'a: 'c, 'b: 'a, 'c>
+{
+  grammar: &'c AGrammar<'a, 'b>,
+  skip_rule: Ident,
+  lexical_rules: HashSet<Ident>,
+  to_rewrite: Vec<usize>
+}
+
+impl<'a, 'b, 'c> Skip<'a, 'b, 'c>
+{
+  pub fn analyse(mut grammar: AGrammar<'a, 'b>) -> Partial<AGrammar<'a, 'b>> {
+    let skip_rule = match grammar.attributes.skip_rule {
+      None => return Value(grammar),
+      Some(rule) => rule
+    };
+    if grammar.rules.iter().all(|r| r.ident() != skip_rule.node) {
+      grammar.span_err(skip_rule.span, format!(
+        "Undeclared skip rule `{}`.", skip_rule.node));
+      return Nothing;
+    }
+    let to_rewrite = Skip::terminals_to_rewrite(&grammar, skip_rule.node);
+    for expr_idx in to_rewrite {
+      Skip::append_skip_call(&mut grammar, expr_idx, skip_rule.node);
+    }
+    Value(grammar)
+  }
+
+  fn terminals_to_rewrite(grammar: &'c AGrammar<'a, 'b>, skip_rule: Ident) -> Vec<usize> {
+    let mut analyser = Skip {
+      grammar: grammar,
+      skip_rule: skip_rule,
+      lexical_rules: Skip::lexical_rules(grammar, skip_rule),
+      to_rewrite: vec![]
+    };
+    for rule in &grammar.rules {
+      if !analyser.lexical_rules.contains(&rule.ident()) {
+        analyser.visit_expr(rule.expr_idx);
+      }
+    }
+    analyser.to_rewrite
+  }
+
+  fn lexical_rules(grammar: &AGrammar<'a, 'b>, skip_rule: Ident) -> HashSet<Ident> {
+    let roots: Vec<Ident> = grammar.rules.iter()
+      .map(|rule| rule.ident())
+      .filter(|rule| *rule == skip_rule || grammar.rule_attributes(*rule).no_skip)
+      .collect();
+    RuleGraph::new(grammar).reachable_from(roots)
+  }
+
+  /// Replace the expression at `expr_idx` by the sequence `expr skip_rule -> (^)`.
+  fn append_skip_call(grammar: &mut AGrammar<'a, 'b>, expr_idx: usize, skip_rule: Ident) {
+    let span = grammar[expr_idx].span();
+    let terminal = grammar.exprs[expr_idx].clone();
+    let terminal_idx = grammar.alloc_expr(span, terminal);
+    let call_idx = grammar.alloc_expr(span, Expression::NonTerminalSymbol(skip_rule));
+    let skip_idx = grammar.alloc_expr(span, Expression::TypeAscription(call_idx, IType::Invisible));
+    grammar.exprs[expr_idx] = Expression::Sequence(vec![terminal_idx, skip_idx]);
+  }
+}
+
+impl<'a, 'b, 'c> ExprByIndex for Skip<'a, 'b, 'c>
+{
+  fn expr_by_index(&self, index: usize) -> Expression {
+    self.grammar.expr_by_index(index).clone()
+  }
+}
+
+impl<'a, 'b, 'c> Visitor<()> for Skip<'a, 'b, 'c>
+{
+  unit_visitor_impl!(sequence);
+  unit_visitor_impl!(choice);
+
+  fn visit_str_literal(&mut self, this: usize, lit: String) {
+    if !lit.is_empty() {
+      self.to_rewrite.push(this);
+    }
+  }
+
+  fn visit_atom(&mut self, this: usize) {
+    self.to_rewrite.push(this);
+  }
+
+  fn visit_non_terminal_symbol(&mut self, this: usize, rule: Ident) {
+    if rule != self.skip_rule && self.lexical_rules.contains(&rule) {
+      self.to_rewrite.push(this);
+    }
+  }
+
+  fn visit_syntactic_predicate(&mut self, _this: usize, _child: usize) {}
+}
@@ -13,6 +13,7 @@
 // limitations under the License.
 
 use middle::analysis::ast::*;
+use rust;
 
 pub struct UndeclaredAction<'a: 'c, 'b: 'a, 'c>
 {
@@ -57,13 +58,44 @@ impl<'a, 'b, 'c> Visitor<()> for UndeclaredAction<'a, 'b, 'c>
   unit_visitor_impl!(choice);
   unit_visitor_impl!(non_terminal);
 
-  fn visit_semantic_action(&mut self, this: usize, _child: usize, action: Ident) {
-    if !self.grammar.rust_functions.contains_key(&action) {
-      self.grammar.expr_err(
-        this,
-        format!("Undeclared action `{}`. Function must be declared in the grammar scope.", action)
-      );
-      self.has_undeclared = true;
+  fn visit_semantic_action(&mut self, this: usize, _child: usize, action: RustAction) {
+    match action {
+      RustAction::Function(ident) => {
+        if !self.grammar.rust_functions.contains_key(&ident) {
+          self.grammar.expr_err(
+            this,
+            format!("Undeclared action `{}`. Function must be declared in the grammar scope.", ident)
+          );
+          self.has_undeclared = true;
+        }
+      }
+      RustAction::Path(path) => {
+        if self.grammar.path_action_type(&path).is_none() {
+          self.grammar.expr_err(
+            this,
+            format!("Cannot infer the type of the action `{}`. A path action must be the variant of \
+              a non-generic enumeration declared in the grammar scope. \
+              Otherwise, use a closure with an explicit return type such as `> |x| -> T {{ ... }}`.",
+              rust::path_to_string(&path))
+          );
+          self.has_undeclared = true;
+        }
+      }
+      RustAction::Closure(closure) => {
+        if let rust::ExprKind::Closure(_, ref decl, _, _) = closure.node {
+          if let rust::FunctionRetTy::Default(_) = decl.output {
+            self.grammar.expr_err(
+              this,
+              format!("Missing return type in the closure action `{}`. \
+                The type of a closure action must be explicit such as in `> |x| -> T {{ ... }}`.",
+                rust::expr_to_string(&closure))
+            );
+            self.has_undeclared = true;
+          }
+        }
+      }
+      // Constructors are generated with the type they build, after this analysis.
+      RustAction::Constructor(..) => ()
     }
   }
 }
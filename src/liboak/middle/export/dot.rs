@@ -0,0 +1,78 @@
+// Copyright 2017 Pierre Talbot (IRCAM)
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Exports the rule call graph in the DOT language of Graphviz.
+//!
+//! Each rule is a node labelled with the type of its value in the generated code, so the invisible types are displayed as `()`. The calls belonging to a cycle of recursive calls are drawn in red, the rules whose type has been reduced to `(^)` because it is infinitely recursive are filled in red, and the unreachable rules are greyed out. The recursive paths responsible for the `(^)` types are listed in the label of the graph.
+
+use middle::typing::ast::*;
+use rule_graph::*;
+use std::collections::HashSet;
+
+pub struct Dot<'a: 'c, 'b: 'a, 'c>
+{
+  grammar: &'c TGrammar<'a, 'b>,
+  graph: RuleGraph
+}
+
+impl<'a, 'b, 'c> Dot<'a, 'b, 'c>
+{
+  pub fn export(grammar: &'c TGrammar<'a, 'b>, rec_rules: &HashSet<Ident>, rec_set: &RecSet) -> String {
+    let dot = Dot {
+      grammar: grammar,
+      graph: RuleGraph::new(grammar)
+    };
+    let mut out = format!("digraph {} {{\n", grammar.name);
+    out.push_str("  node [shape=box, fontname=\"monospace\"];\n");
+    if !rec_set.is_empty() {
+      let mut report = format!("Infinite recursive types reduced to (^):\\l");
+      for path in &rec_set.path_set {
+        report.push_str(format!("{}\\l", escape(path.display())).as_str());
+      }
+      out.push_str(format!("  labelloc=\"t\";\n  label=\"{}\";\n", report).as_str());
+    }
+    for rule in &grammar.rules {
+      out.push_str(dot.node(*rule, rec_rules.contains(&rule.ident())).as_str());
+    }
+    for rule in &grammar.rules {
+      for callee in dot.graph.callees(rule.ident()) {
+        let style = if dot.is_recursive_call(rule.ident(), callee) { " [color=red]" } else { "" };
+        out.push_str(format!("  {} -> {}{};\n", rule.ident(), callee, style).as_str());
+      }
+    }
+    out.push_str("}\n");
+    out
+  }
+
+  fn node(&self, rule: Rule, rec_type: bool) -> String {
+    let ty = self.grammar.display_type(rule.expr_idx);
+    let mut attributes = vec![format!("label=\"{}\\n{}\"", rule.ident(), escape(ty))];
+    if rec_type {
+      attributes.push(format!("style=filled, fillcolor=\"#f4cccc\""));
+    }
+    if !self.grammar.is_reachable_rule(rule.ident()) {
+      attributes.push(format!("color=grey, fontcolor=grey"));
+    }
+    format!("  {} [{}];\n", rule.ident(), attributes.join(", "))
+  }
+
+  /// The call is part of a cycle if `caller` can be reached from `callee`.
+  fn is_recursive_call(&self, caller: Ident, callee: Ident) -> bool {
+    self.graph.reachable_from(vec![callee]).contains(&caller)
+  }
+}
+
+fn escape(text: String) -> String {
+  text.replace('\\', "\\\\").replace('"', "\\\"")
+}
@@ -0,0 +1,249 @@
+// Copyright 2017 Pierre Talbot (IRCAM)
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Exports the grammar in the ISO EBNF notation (ISO/IEC 14977) or in the EBNF notation of the W3C (used in the XML specification).
+//!
+//! The rules are exported in their declaration order. Both notations describe unordered choices, so the file starts with a comment recalling that choices are ordered and listing the constructs that are lost. Syntactic predicates, semantic predicates and cuts do not consume input and have no equivalent in these notations, they are exported as comments, except in the W3C notation for `!B A` where `A` and `B` match a single character: it is exported as the exception `A - B`, which matches the same character. Semantic actions, type ascriptions and spans are ignored.
+
+use middle::analysis::ast::*;
+use std::collections::HashSet;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Notation
+{
+  Iso,
+  W3c
+}
+
+/// Binding power of the exported expressions, used to decide where parenthesis are needed.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Precedence
+{
+  Alternation,
+  Concatenation,
+  Primary
+}
+
+pub struct Ebnf<'a: 'c, 'b: 'a, 'c>
+{
+  grammar: &'c AGrammar<'a, 'b>,
+  notation: Notation
+}
+
+impl<'a, 'b, 'c> Ebnf<'a, 'b, 'c>
+{
+  pub fn export(grammar: &'c AGrammar<'a, 'b>, notation: Notation) -> String {
+    let ebnf = Ebnf::new(grammar, notation);
+    let predicates = match notation {
+      Notation::Iso => "Syntactic predicates, semantic predicates and cuts are written as comments",
+      Notation::W3c => "Syntactic predicates, semantic predicates and cuts are written as comments, except `!B A` matching a single character which is written `A - B`"
+    };
+    let mut out = ebnf.comment(format!(
+      "Grammar `{}` exported by Oak. Choices are ordered: the first alternative that matches is chosen. {}; semantic actions and types are dropped.",
+      grammar.name, predicates));
+    out.push_str("\n\n");
+    for rule in &grammar.rules {
+      let body = ebnf.expr(rule.expr_idx);
+      match notation {
+        Notation::Iso => out.push_str(format!("{} = {} ;\n", rule.ident(), body).as_str()),
+        Notation::W3c => out.push_str(format!("{} ::= {}\n", rule.ident(), body).as_str())
+      }
+    }
+    out
+  }
+
+  pub fn new(grammar: &'c AGrammar<'a, 'b>, notation: Notation) -> Self {
+    Ebnf {
+      grammar: grammar,
+      notation: notation
+    }
+  }
+
+  /// The expression `expr_idx` in the notation of `self`.
+  pub fn expr(&self, expr_idx: usize) -> String {
+    self.expr_prec(expr_idx).0
+  }
+
+  fn expr_prec(&self, expr_idx: usize) -> (String, Precedence) {
+    use self::Notation::*;
+    use self::Precedence::{Alternation, Concatenation, Primary};
+    match (self.grammar.expr_by_index(expr_idx), self.notation) {
+      (StrLiteral(lit), _) => (self.literal(lit), Primary),
+      (AnySingleChar, Iso) => (format!("? any character ?"), Primary),
+      (AnySingleChar, W3c) => (format!("[#x0-#x10FFFF]"), Primary),
+      (CharacterClass(class), Iso) => (format!("? {} ?", class), Primary),
+      (CharacterClass(class), W3c) => (self.w3c_class(class), Primary),
+      (NonTerminalSymbol(rule), _) => (format!("{}", rule), Primary),
+      (Sequence(children), _) => self.sequence(children),
+      (Choice(branches), _) => {
+        let branches: Vec<_> = branches.into_iter()
+          .map(|branch| self.expr_at_least(branch, Concatenation))
+          .collect();
+        (branches.join(" | "), Alternation)
+      }
+      (ZeroOrMore(child), Iso) => (format!("{{ {} }}", self.expr(child)), Primary),
+      (OneOrMore(child), Iso) => {
+        let child = self.expr_at_least(child, Primary);
+        (format!("{} , {{ {} }}", child, child), Concatenation)
+      }
+      (ZeroOrOne(child), Iso) => (format!("[ {} ]", self.expr(child)), Primary),
+      (ZeroOrMore(child), W3c) => (format!("{}*", self.expr_at_least(child, Primary)), Primary),
+      (OneOrMore(child), W3c) => (format!("{}+", self.expr_at_least(child, Primary)), Primary),
+      (ZeroOrOne(child), W3c) => (format!("{}?", self.expr_at_least(child, Primary)), Primary),
+      (NotPredicate(child), _) => (self.comment(format!("!{}", self.expr_at_least(child, Primary))), Primary),
+      (AndPredicate(child), _) => (self.comment(format!("&{}", self.expr_at_least(child, Primary))), Primary),
+      (SemanticPredicate(child, _), _) => {
+        let child = self.expr_at_least(child, Primary);
+        (format!("{} {}", child, self.comment(format!("semantic predicate"))), Concatenation)
+      }
+      (Cut, _) => (self.comment(format!("cut")), Primary),
+      (SemanticAction(child, _), _)
+    | (TypeAscription(child, _), _)
+    | (SpannedExpr(child), _) => self.expr_prec(child)
+    }
+  }
+
+  /// Expressions exported as comments are not separated from the other elements of the sequence. The exceptions `A - B` are parenthesized unless they are the whole sequence.
+  fn sequence(&self, children: Vec<usize>) -> (String, Precedence) {
+    let separator = if self.notation == Notation::Iso { " , " } else { " " };
+    let mut out = String::new();
+    let mut has_element = false;
+    let mut i = 0;
+    while i < children.len() {
+      let child = children[i];
+      let (element, is_comment) = match self.exception(child, children.get(i + 1).cloned()) {
+        Some(exception) => {
+          if children.len() == 2 {
+            return (exception, Precedence::Alternation);
+          }
+          i += 1;
+          (format!("( {} )", exception), false)
+        }
+        None => (self.expr_at_least(child, Precedence::Concatenation), self.is_comment(child))
+      };
+      if !out.is_empty() {
+        out.push_str(if has_element && !is_comment { separator } else { " " });
+      }
+      out.push_str(element.as_str());
+      has_element |= !is_comment;
+      i += 1;
+    }
+    (out, Precedence::Concatenation)
+  }
+
+  /// `!B A` is written `A - B` in the W3C notation when `A` and `B` match a single character, since `B` then rejects exactly the characters matched by `A` that it matches.
+  fn exception(&self, child: usize, next: Option<usize>) -> Option<String> {
+    match (self.notation, self.grammar.expr_by_index(child), next) {
+      (Notation::W3c, NotPredicate(except), Some(next)) => {
+        if self.is_single_char(except, &mut HashSet::new()) && self.is_single_char(next, &mut HashSet::new()) {
+          Some(format!("{} - {}",
+            self.expr_at_least(next, Precedence::Primary),
+            self.expr_at_least(except, Precedence::Primary)))
+        }
+        else { None }
+      }
+      _ => None
+    }
+  }
+
+  fn is_single_char(&self, expr_idx: usize, visiting: &mut HashSet<Ident>) -> bool {
+    match self.grammar.expr_by_index(expr_idx) {
+      StrLiteral(lit) => lit.chars().count() == 1,
+      AnySingleChar
+    | CharacterClass(_) => true,
+      Choice(branches) => branches.into_iter().all(|branch| self.is_single_char(branch, visiting)),
+      NonTerminalSymbol(rule) => {
+        if visiting.insert(rule) {
+          let single_char = self.is_single_char(self.grammar.expr_index_of_rule(rule), visiting);
+          visiting.remove(&rule);
+          single_char
+        }
+        else { false }
+      }
+      SemanticAction(child, _)
+    | TypeAscription(child, _)
+    | SpannedExpr(child) => self.is_single_char(child, visiting),
+      _ => false
+    }
+  }
+
+  fn is_comment(&self, expr_idx: usize) -> bool {
+    match self.grammar.expr_by_index(expr_idx) {
+      NotPredicate(_)
+    | AndPredicate(_)
+    | Cut => true,
+      _ => false
+    }
+  }
+
+  /// Parenthesize the expression if it binds less than `prec`.
+  fn expr_at_least(&self, expr_idx: usize, prec: Precedence) -> String {
+    let (expr, expr_prec) = self.expr_prec(expr_idx);
+    if expr_prec < prec { format!("( {} )", expr) }
+    else { expr }
+  }
+
+  fn literal(&self, lit: String) -> String {
+    if !lit.contains('"') {
+      format!("\"{}\"", lit)
+    }
+    else if !lit.contains('\'') {
+      format!("'{}'", lit)
+    }
+    else {
+      // A literal cannot contain both quotes, so it is split in a sequence of literals around `"`.
+      let mut parts = vec![];
+      for (i, part) in lit.split('"').enumerate() {
+        if i > 0 {
+          parts.push(format!("'\"'"));
+        }
+        if !part.is_empty() {
+          parts.push(format!("\"{}\"", part));
+        }
+      }
+      let separator = if self.notation == Notation::Iso { " , " } else { " " };
+      format!("( {} )", parts.join(separator))
+    }
+  }
+
+  fn w3c_class(&self, class: CharacterClassExpr) -> String {
+    let mut out = String::from("[");
+    for interval in class.intervals {
+      out.push_str(w3c_char(interval.lo).as_str());
+      if interval.lo != interval.hi {
+        out.push('-');
+        out.push_str(w3c_char(interval.hi).as_str());
+      }
+    }
+    out.push(']');
+    out
+  }
+
+  fn comment(&self, text: String) -> String {
+    match self.notation {
+      Notation::Iso => format!("(* {} *)", text.replace("*)", "* )")),
+      Notation::W3c => format!("/* {} */", text.replace("*/", "* /"))
+    }
+  }
+}
+
+/// Characters with a meaning in W3C character classes are written with their code point.
+fn w3c_char(c: char) -> String {
+  if c.is_alphanumeric() || (c > ' ' && c <= '~' && !"[]-^#\\".contains(c)) {
+    c.to_string()
+  }
+  else {
+    format!("#x{:X}", c as u32)
+  }
+}
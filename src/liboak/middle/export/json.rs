@@ -0,0 +1,100 @@
+// Copyright 2017 Pierre Talbot (IRCAM)
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Minimal JSON values for the exporters producing JSON files.
+
+pub enum Json
+{
+  Str(String),
+  Array(Vec<Json>),
+  /// Fields are printed in their insertion order.
+  Object(Vec<(String, Json)>)
+}
+
+impl Json
+{
+  pub fn str(value: &str) -> Json {
+    Json::Str(String::from(value))
+  }
+
+  pub fn object(fields: Vec<(&str, Json)>) -> Json {
+    Json::Object(fields.into_iter()
+      .map(|(name, value)| (String::from(name), value))
+      .collect())
+  }
+
+  pub fn pretty(&self) -> String {
+    let mut out = String::new();
+    self.write(&mut out, 0);
+    out.push('\n');
+    out
+  }
+
+  fn write(&self, out: &mut String, indent: usize) {
+    match self {
+      &Json::Str(ref value) => out.push_str(escape(value).as_str()),
+      &Json::Array(ref values) => {
+        Json::write_seq(out, indent, '[', ']', values.iter().map(|value| (None, value)).collect())
+      }
+      &Json::Object(ref fields) => {
+        Json::write_seq(out, indent, '{', '}', fields.iter().map(|&(ref name, ref value)| (Some(name), value)).collect())
+      }
+    }
+  }
+
+  fn write_seq(out: &mut String, indent: usize, open: char, close: char, elements: Vec<(Option<&String>, &Json)>) {
+    out.push(open);
+    if !elements.is_empty() {
+      let len = elements.len();
+      for (i, (name, value)) in elements.into_iter().enumerate() {
+        out.push('\n');
+        push_indent(out, indent + 1);
+        if let Some(name) = name {
+          out.push_str(escape(name).as_str());
+          out.push_str(": ");
+        }
+        value.write(out, indent + 1);
+        if i + 1 < len {
+          out.push(',');
+        }
+      }
+      out.push('\n');
+      push_indent(out, indent);
+    }
+    out.push(close);
+  }
+}
+
+fn push_indent(out: &mut String, indent: usize) {
+  for _ in 0..indent {
+    out.push_str("  ");
+  }
+}
+
+fn escape(value: &String) -> String {
+  let mut out = String::from("\"");
+  for c in value.chars() {
+    match c {
+      '"' => out.push_str("\\\""),
+      '\\' => out.push_str("\\\\"),
+      '\n' => out.push_str("\\n"),
+      '\r' => out.push_str("\\r"),
+      '\t' => out.push_str("\\t"),
+      c if (c as u32) < 0x20 => out.push_str(format!("\\u{:04x}", c as u32).as_str()),
+      c => out.push(c)
+    }
+  }
+  out.push('"');
+  out
+}
@@ -0,0 +1,80 @@
+// Copyright 2017 Pierre Talbot (IRCAM)
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Exports the grammar in the files requested with the attributes `#![export_<format> = "path"]`. The exported grammar is the grammar checked by the analysis, so it includes the calls to the skip rule. The call graph (`#![export_dot]`) is exported after the typing since it shows the inferred types.
+
+use middle::analysis::ast::*;
+use middle::typing::ast::{TGrammar, RecSet};
+use middle::export::ebnf::*;
+use middle::export::railroad::*;
+use middle::export::dot::*;
+use middle::export::tree_sitter::*;
+use middle::export::textmate::*;
+use partial::Partial::*;
+
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::Write;
+
+mod ebnf;
+mod railroad;
+mod dot;
+mod json;
+mod regex;
+mod tree_sitter;
+mod textmate;
+
+pub fn export_grammar<'a, 'b>(grammar: AGrammar<'a, 'b>) -> Partial<AGrammar<'a, 'b>> {
+  let mut exported = true;
+  for export in grammar.attributes.exports.clone() {
+    let content = match export.format {
+      ExportFormat::Ebnf => Ebnf::export(&grammar, Notation::Iso),
+      ExportFormat::W3cEbnf => Ebnf::export(&grammar, Notation::W3c),
+      ExportFormat::Railroad => Railroad::export(&grammar),
+      ExportFormat::TreeSitter => TreeSitter::export(&grammar),
+      ExportFormat::TextMate => TextMate::export(&grammar),
+      ExportFormat::Dot => continue
+    };
+    exported &= write_export(&grammar, &export, content);
+  }
+  if exported { Value(grammar) }
+  else { Nothing }
+}
+
+/// `rec_rules` are the rules whose type is reduced to `(^)` because of the recursive paths in `rec_set`.
+pub fn export_typed_grammar<'a, 'b>(grammar: TGrammar<'a, 'b>, rec_rules: HashSet<Ident>,
+  rec_set: RecSet) -> Partial<TGrammar<'a, 'b>>
+{
+  let mut exported = true;
+  for export in &grammar.attributes.exports {
+    if export.format == ExportFormat::Dot {
+      exported &= write_export(&grammar, export, Dot::export(&grammar, &rec_rules, &rec_set));
+    }
+  }
+  if exported { Value(grammar) }
+  else { Nothing }
+}
+
+fn write_export<ExprInfo>(grammar: &Grammar<ExprInfo>, export: &Export, content: String) -> bool {
+  let result = File::create(export.path.as_str())
+    .and_then(|mut file| file.write_all(content.as_bytes()));
+  match result {
+    Ok(()) => true,
+    Err(err) => {
+      grammar.span_err(export.span, format!(
+        "Cannot export the grammar in `{}`: {}.", export.path, err));
+      false
+    }
+  }
+}
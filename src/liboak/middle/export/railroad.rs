@@ -0,0 +1,241 @@
+// Copyright 2017 Pierre Talbot (IRCAM)
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Exports the grammar as an HTML page with one railroad diagram (in SVG) per rule.
+//!
+//! The branches of a choice are drawn from top to bottom in the order they are tried. Syntactic predicates, semantic predicates and cuts are drawn in dashed boxes since they do not consume input. The boxes of the rules are links to their diagrams.
+
+use middle::analysis::ast::*;
+use middle::export::ebnf::*;
+
+use std::cmp::max;
+
+/// Height of a box above (and below) the line.
+const BOX_HALF_HEIGHT: usize = 11;
+const CHAR_WIDTH: usize = 8;
+/// Horizontal space between two elements of a sequence.
+const GAP: usize = 10;
+/// Horizontal space taken by the lines entering or leaving a choice or a loop.
+const TRACK: usize = 20;
+
+enum Diagram
+{
+  Terminal(String),
+  NonTerminal(String),
+  /// Expression that does not consume input.
+  Comment(String),
+  Skip,
+  Sequence(Vec<Diagram>),
+  Choice(Vec<Diagram>),
+  /// One or more repetitions.
+  Loop(Box<Diagram>)
+}
+
+/// Dimensions of a diagram relative to the line entering and leaving it.
+#[derive(Clone, Copy)]
+struct Size
+{
+  width: usize,
+  up: usize,
+  down: usize
+}
+
+impl Size
+{
+  fn new(width: usize, up: usize, down: usize) -> Size {
+    Size {
+      width: width,
+      up: up,
+      down: down
+    }
+  }
+}
+
+impl Diagram
+{
+  fn size(&self) -> Size {
+    match self {
+      &Diagram::Terminal(ref text)
+    | &Diagram::NonTerminal(ref text)
+    | &Diagram::Comment(ref text) =>
+        Size::new(text.chars().count() * CHAR_WIDTH + 2 * GAP, BOX_HALF_HEIGHT, BOX_HALF_HEIGHT),
+      &Diagram::Skip => Size::new(0, 0, 0),
+      &Diagram::Sequence(ref children) => {
+        let sizes: Vec<_> = children.iter().map(|child| child.size()).collect();
+        Size::new(
+          sizes.iter().map(|size| size.width).sum::<usize>() + GAP * (max(sizes.len(), 1) - 1),
+          sizes.iter().map(|size| size.up).max().unwrap_or(0),
+          sizes.iter().map(|size| size.down).max().unwrap_or(0))
+      }
+      &Diagram::Choice(ref branches) => {
+        let sizes: Vec<_> = branches.iter().map(|branch| branch.size()).collect();
+        Size::new(
+          sizes.iter().map(|size| size.width).max().unwrap_or(0) + 2 * TRACK,
+          sizes[0].up,
+          sizes[0].down + sizes[1..].iter().map(|size| GAP + size.up + size.down).sum::<usize>())
+      }
+      &Diagram::Loop(ref child) => {
+        let size = child.size();
+        Size::new(size.width + 2 * TRACK, size.up, size.down + GAP)
+      }
+    }
+  }
+
+  /// Draws the diagram from `(x, y)` to `(x + width, y)`.
+  fn render(&self, x: usize, y: usize, out: &mut String) {
+    let size = self.size();
+    match self {
+      &Diagram::Terminal(ref text) => Diagram::render_box(x, y, size.width, "terminal", text, out),
+      &Diagram::Comment(ref text) => Diagram::render_box(x, y, size.width, "comment", text, out),
+      &Diagram::NonTerminal(ref text) => {
+        out.push_str(format!("<a xlink:href=\"#{}\">", text).as_str());
+        Diagram::render_box(x, y, size.width, "nonterminal", text, out);
+        out.push_str("</a>\n");
+      }
+      &Diagram::Skip => (),
+      &Diagram::Sequence(ref children) => {
+        let mut cx = x;
+        for (i, child) in children.iter().enumerate() {
+          if i > 0 {
+            path(format!("M{} {}h{}", cx, y, GAP), out);
+            cx += GAP;
+          }
+          child.render(cx, y, out);
+          cx += child.size().width;
+        }
+      }
+      &Diagram::Choice(ref branches) => {
+        let inner_width = size.width - 2 * TRACK;
+        let right = x + TRACK + inner_width;
+        let mut by = y;
+        for (i, branch) in branches.iter().enumerate() {
+          let branch_size = branch.size();
+          if i > 0 {
+            by += GAP + branch_size.up;
+          }
+          path(format!("M{} {}h{}V{}h{}", x, y, GAP, by, GAP), out);
+          branch.render(x + TRACK, by, out);
+          path(format!("M{} {}H{}h{}V{}h{}", x + TRACK + branch_size.width, by, right, GAP, y, GAP), out);
+          by += branch_size.down;
+        }
+      }
+      &Diagram::Loop(ref child) => {
+        let child_size = child.size();
+        let right = x + TRACK + child_size.width;
+        path(format!("M{} {}h{}", x, y, TRACK), out);
+        child.render(x + TRACK, y, out);
+        path(format!("M{} {}h{}", right, y, TRACK), out);
+        path(format!("M{} {}V{}H{}V{}", right + GAP, y, y + child_size.down + GAP, x + GAP, y), out);
+      }
+    }
+  }
+
+  fn render_box(x: usize, y: usize, width: usize, class: &str, text: &String, out: &mut String) {
+    let radius = if class == "terminal" { BOX_HALF_HEIGHT } else { 0 };
+    out.push_str(format!(
+      "<rect class=\"{}\" x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" rx=\"{}\"/>\n",
+      class, x, y - BOX_HALF_HEIGHT, width, 2 * BOX_HALF_HEIGHT, radius).as_str());
+    out.push_str(format!(
+      "<text x=\"{}\" y=\"{}\">{}</text>\n",
+      x + width / 2, y + 4, escape_xml(text)).as_str());
+  }
+}
+
+fn path(data: String, out: &mut String) {
+  out.push_str(format!("<path d=\"{}\"/>\n", data).as_str());
+}
+
+fn escape_xml(text: &String) -> String {
+  text.replace('&', "&amp;")
+    .replace('<', "&lt;")
+    .replace('>', "&gt;")
+    .replace('"', "&quot;")
+}
+
+pub struct Railroad<'a: 'c, 'b: 'a, 'c>
+{
+  grammar: &'c AGrammar<'a, 'b>,
+  ebnf: Ebnf<'a, 'b, 'c>
+}
+
+impl<'a, 'b, 'c> Railroad<'a, 'b, 'c>
+{
+  pub fn export(grammar: &'c AGrammar<'a, 'b>) -> String {
+    let railroad = Railroad {
+      grammar: grammar,
+      ebnf: Ebnf::new(grammar, Notation::W3c)
+    };
+    let mut out = format!("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n\
+      <title>{}</title>\n<style>\n\
+      svg {{ font: 14px monospace; }}\n\
+      path {{ fill: none; stroke: black; stroke-width: 2; }}\n\
+      rect {{ fill: #ffc; stroke: black; stroke-width: 2; }}\n\
+      rect.comment {{ fill: none; stroke-dasharray: 4; }}\n\
+      text {{ text-anchor: middle; }}\n\
+      circle {{ fill: black; }}\n\
+      </style>\n</head>\n<body>\n<h1>Grammar <code>{}</code></h1>\n",
+      grammar.name, grammar.name);
+    for rule in &grammar.rules {
+      out.push_str(format!("<h2 id=\"{}\">{}</h2>\n", rule.ident(), rule.ident()).as_str());
+      out.push_str(railroad.render_rule(railroad.diagram(rule.expr_idx)).as_str());
+    }
+    out.push_str("</body>\n</html>\n");
+    out
+  }
+
+  fn render_rule(&self, diagram: Diagram) -> String {
+    let size = diagram.size();
+    let y = size.up + GAP;
+    let width = size.width + 2 * TRACK;
+    let mut out = format!(
+      "<svg xmlns=\"http://www.w3.org/2000/svg\" xmlns:xlink=\"http://www.w3.org/1999/xlink\" width=\"{}\" height=\"{}\">\n",
+      width, size.up + size.down + 2 * GAP);
+    out.push_str(format!("<circle cx=\"5\" cy=\"{}\" r=\"4\"/>\n", y).as_str());
+    path(format!("M5 {}H{}", y, TRACK), &mut out);
+    diagram.render(TRACK, y, &mut out);
+    path(format!("M{} {}H{}", TRACK + size.width, y, width - 5), &mut out);
+    out.push_str(format!("<circle cx=\"{}\" cy=\"{}\" r=\"4\"/>\n", width - 5, y).as_str());
+    out.push_str("</svg>\n");
+    out
+  }
+
+  fn diagram(&self, expr_idx: usize) -> Diagram {
+    match self.grammar.expr_by_index(expr_idx) {
+      StrLiteral(lit) => Diagram::Terminal(format!("\"{}\"", lit)),
+      AnySingleChar => Diagram::Terminal(format!(".")),
+      CharacterClass(class) => Diagram::Terminal(format!("{}", class)),
+      NonTerminalSymbol(rule) => Diagram::NonTerminal(format!("{}", rule)),
+      Sequence(children) => Diagram::Sequence(children.into_iter()
+        .map(|child| self.diagram(child))
+        .collect()),
+      Choice(branches) => Diagram::Choice(branches.into_iter()
+        .map(|branch| self.diagram(branch))
+        .collect()),
+      ZeroOrMore(child) => Diagram::Choice(vec![
+        Diagram::Loop(Box::new(self.diagram(child))),
+        Diagram::Skip]),
+      OneOrMore(child) => Diagram::Loop(Box::new(self.diagram(child))),
+      ZeroOrOne(child) => Diagram::Choice(vec![self.diagram(child), Diagram::Skip]),
+      NotPredicate(child) => Diagram::Comment(format!("!{}", self.ebnf.expr(child))),
+      AndPredicate(child) => Diagram::Comment(format!("&{}", self.ebnf.expr(child))),
+      SemanticPredicate(child, _) => Diagram::Sequence(vec![
+        self.diagram(child),
+        Diagram::Comment(format!("semantic predicate"))]),
+      Cut => Diagram::Comment(format!("^")),
+      SemanticAction(child, _)
+    | TypeAscription(child, _)
+    | SpannedExpr(child) => self.diagram(child)
+    }
+  }
+}
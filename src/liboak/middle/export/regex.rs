@@ -0,0 +1,48 @@
+// Copyright 2017 Pierre Talbot (IRCAM)
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Regular expressions of the terminals, shared by the tree-sitter and TextMate exporters.
+
+use middle::analysis::ast::*;
+
+pub fn literal_regex(lit: &String) -> String {
+  lit.chars().map(|c| escape_char(c, "\\^$.|?*+()[]{}/")).collect()
+}
+
+pub fn class_regex(class: &CharacterClassExpr) -> String {
+  let mut regex = String::from("[");
+  for interval in &class.intervals {
+    regex.push_str(class_char(interval.lo).as_str());
+    if interval.lo != interval.hi {
+      regex.push('-');
+      regex.push_str(class_char(interval.hi).as_str());
+    }
+  }
+  regex.push(']');
+  regex
+}
+
+fn class_char(c: char) -> String {
+  escape_char(c, "\\]^-[")
+}
+
+fn escape_char(c: char, special: &str) -> String {
+  match c {
+    '\n' => format!("\\n"),
+    '\r' => format!("\\r"),
+    '\t' => format!("\\t"),
+    c if special.contains(c) => format!("\\{}", c),
+    c => c.to_string()
+  }
+}
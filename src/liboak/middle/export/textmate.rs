@@ -0,0 +1,136 @@
+// Copyright 2017 Pierre Talbot (IRCAM)
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Exports the rules annotated with `#[scope = "..."]` as a TextMate grammar (`.tmLanguage.json`).
+//!
+//! TextMate grammars highlight tokens matched by regular expressions, so only the token-level rules can be exported: each scoped rule becomes a regular expression in which the rules it calls are inlined, and the syntactic predicates are lookaheads. Recursive rules and semantic predicates cannot be translated, a warning is emitted and the rule is not exported (for a recursive rule) or the predicate is ignored. The patterns are tried in the declaration order of the rules.
+
+use middle::analysis::ast::*;
+use middle::export::json::*;
+use middle::export::json::Json::*;
+use middle::export::regex::*;
+
+/// Binding power of the regular expressions, used to decide where groups are needed.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Precedence
+{
+  Alternation,
+  Concatenation,
+  Atom
+}
+
+pub struct TextMate<'a: 'c, 'b: 'a, 'c>
+{
+  grammar: &'c AGrammar<'a, 'b>
+}
+
+impl<'a, 'b, 'c> TextMate<'a, 'b, 'c>
+{
+  pub fn export(grammar: &'c AGrammar<'a, 'b>) -> String {
+    let exporter = TextMate { grammar: grammar };
+    let mut patterns = vec![];
+    let mut repository = vec![];
+    for rule in &grammar.rules {
+      if let Some(scope) = grammar.rule_attributes(rule.ident()).scope {
+        if let Some(regex) = exporter.regex(rule.expr_idx, &mut vec![rule.ident()]) {
+          let name = format!("{}", rule.ident());
+          patterns.push(Json::object(vec![("include", Json::Str(format!("#{}", name)))]));
+          repository.push((name, Json::object(vec![
+            ("name", Json::Str(scope)),
+            ("match", Json::Str(regex.0))
+          ])));
+        }
+      }
+    }
+    if patterns.is_empty() {
+      grammar.warn(format!(
+        "The TextMate grammar exported from `{}` is empty: annotate the token rules with `#[scope = \"...\"]`.",
+        grammar.name));
+    }
+    Json::object(vec![
+      ("name", Json::Str(format!("{}", grammar.name))),
+      ("scopeName", Json::Str(format!("source.{}", grammar.name))),
+      ("patterns", Array(patterns)),
+      ("repository", Object(repository))
+    ]).pretty()
+  }
+
+  /// `visiting` contains the rules being inlined, `None` if the expression cannot be translated.
+  fn regex(&self, expr_idx: usize, visiting: &mut Vec<Ident>) -> Option<(String, Precedence)> {
+    use self::Precedence::*;
+    match self.grammar.expr_by_index(expr_idx) {
+      StrLiteral(lit) => {
+        let prec = if lit.chars().count() == 1 { Atom } else { Concatenation };
+        Some((literal_regex(&lit), prec))
+      }
+      AnySingleChar => Some((format!("."), Atom)),
+      CharacterClass(class) => Some((class_regex(&class), Atom)),
+      NonTerminalSymbol(rule) => {
+        if visiting.contains(&rule) {
+          self.grammar.cx.span_warn(self.grammar[expr_idx].span(), format!(
+            "The recursive call to `{}` cannot be exported to a TextMate regular expression.", rule).as_str());
+          None
+        }
+        else {
+          visiting.push(rule);
+          let regex = self.regex(self.grammar.expr_index_of_rule(rule), visiting);
+          visiting.pop();
+          regex
+        }
+      }
+      Sequence(children) => {
+        let mut regex = String::new();
+        for child in children {
+          regex.push_str(self.regex_at_least(child, Concatenation, visiting)?.as_str());
+        }
+        Some((regex, Concatenation))
+      }
+      Choice(branches) => {
+        let mut alternatives = vec![];
+        for branch in branches {
+          alternatives.push(self.regex_at_least(branch, Concatenation, visiting)?);
+        }
+        Some((alternatives.join("|"), Alternation))
+      }
+      ZeroOrMore(child) => self.postfix(child, "*", visiting),
+      OneOrMore(child) => self.postfix(child, "+", visiting),
+      ZeroOrOne(child) => self.postfix(child, "?", visiting),
+      NotPredicate(child) => self.regex(child, visiting).map(|(regex, _)| (format!("(?!{})", regex), Atom)),
+      AndPredicate(child) => self.regex(child, visiting).map(|(regex, _)| (format!("(?={})", regex), Atom)),
+      SemanticPredicate(child, _) => {
+        self.grammar.cx.span_warn(self.grammar[expr_idx].span(),
+          "The semantic predicate cannot be exported to a TextMate regular expression and is ignored.");
+        self.regex(child, visiting)
+      }
+      Cut => Some((String::new(), Atom)),
+      SemanticAction(child, _)
+    | TypeAscription(child, _)
+    | SpannedExpr(child) => self.regex(child, visiting)
+    }
+  }
+
+  fn postfix(&self, child: usize, op: &str, visiting: &mut Vec<Ident>) -> Option<(String, Precedence)> {
+    self.regex_at_least(child, Precedence::Atom, visiting)
+      // Nested quantifiers such as `a**` are possessive in Oniguruma, so the result is grouped when repeated again.
+      .map(|regex| (format!("{}{}", regex, op), Precedence::Concatenation))
+  }
+
+  /// Groups the regular expression if it binds less than `prec`.
+  fn regex_at_least(&self, expr_idx: usize, prec: Precedence, visiting: &mut Vec<Ident>) -> Option<String> {
+    self.regex(expr_idx, visiting).map(|(regex, regex_prec)| {
+      if regex_prec < prec { format!("(?:{})", regex) }
+      else { regex }
+    })
+  }
+}
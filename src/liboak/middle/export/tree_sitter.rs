@@ -0,0 +1,143 @@
+// Copyright 2017 Pierre Talbot (IRCAM)
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Exports the grammar as a tree-sitter `grammar.json`.
+//!
+//! The first entry rule (or the first rule) becomes the start rule of tree-sitter. The skip rule is not exported: if its body is `e*` or `e+`, `e` becomes the `extras` of the tree-sitter grammar. The lexical rules annotated with `#[no_skip]` which do not call other rules are exported as tokens. Tree-sitter has no ordered choice, no predicates and no cut, so syntactic predicates and cuts are ignored, and the semantic predicates are replaced by the expression they check, with a warning for each of them.
+
+use middle::analysis::ast::*;
+use middle::export::json::*;
+use middle::export::json::Json::*;
+use middle::export::regex::*;
+use rule_graph::*;
+
+pub struct TreeSitter<'a: 'c, 'b: 'a, 'c>
+{
+  grammar: &'c AGrammar<'a, 'b>,
+  skip_rule: Option<Ident>
+}
+
+impl<'a, 'b, 'c> TreeSitter<'a, 'b, 'c>
+{
+  pub fn export(grammar: &'c AGrammar<'a, 'b>) -> String {
+    let exporter = TreeSitter {
+      grammar: grammar,
+      skip_rule: grammar.attributes.skip_rule.map(|rule| rule.node)
+    };
+    let graph = RuleGraph::new(grammar);
+    let mut rules: Vec<Rule> = grammar.rules.iter()
+      .filter(|rule| Some(rule.ident()) != exporter.skip_rule)
+      .cloned()
+      .collect();
+    if let Some(pos) = rules.iter().position(|rule| grammar.rule_attributes(rule.ident()).entry) {
+      let start = rules.remove(pos);
+      rules.insert(0, start);
+    }
+    let rules_json = rules.into_iter()
+      .map(|rule| {
+        let body = exporter.expr(rule.expr_idx).unwrap_or(TreeSitter::blank());
+        let is_token = grammar.rule_attributes(rule.ident()).no_skip
+          && graph.callees(rule.ident()).is_empty();
+        let body = if is_token { Json::object(vec![("type", Json::str("TOKEN")), ("content", body)]) }
+                   else { body };
+        (format!("{}", rule.ident()), body)
+      })
+      .collect();
+    Json::object(vec![
+      ("name", Json::Str(format!("{}", grammar.name))),
+      ("rules", Object(rules_json)),
+      ("extras", Array(exporter.extras()))
+    ]).pretty()
+  }
+
+  fn extras(&self) -> Vec<Json> {
+    match self.skip_rule {
+      None => vec![],
+      Some(skip_rule) => {
+        let body = self.grammar.expr_index_of_rule(skip_rule);
+        match self.grammar.expr_by_index(body) {
+          ZeroOrMore(child)
+        | OneOrMore(child) => self.expr(child).into_iter().collect(),
+          _ => {
+            self.grammar.cx.span_warn(self.grammar[body].span(),
+              "The skip rule cannot be exported to tree-sitter: its body must be of the form `e*` to become the extras `e`.");
+            vec![]
+          }
+        }
+      }
+    }
+  }
+
+  /// `None` if the expression does not match any text in tree-sitter.
+  fn expr(&self, expr_idx: usize) -> Option<Json> {
+    match self.grammar.expr_by_index(expr_idx) {
+      StrLiteral(ref lit) if lit.is_empty() => None,
+      StrLiteral(lit) => Some(TreeSitter::node("STRING", "value", Json::Str(lit))),
+      AnySingleChar => Some(TreeSitter::node("PATTERN", "value", Json::str("[\\s\\S]"))),
+      CharacterClass(class) => Some(TreeSitter::node("PATTERN", "value", Json::Str(class_regex(&class)))),
+      NonTerminalSymbol(rule) => {
+        if Some(rule) == self.skip_rule { None }
+        else { Some(TreeSitter::node("SYMBOL", "name", Json::Str(format!("{}", rule)))) }
+      }
+      Sequence(children) => {
+        let mut members: Vec<_> = children.into_iter()
+          .filter_map(|child| self.expr(child))
+          .collect();
+        match members.len() {
+          0 => None,
+          1 => members.pop(),
+          _ => Some(TreeSitter::node("SEQ", "members", Array(members)))
+        }
+      }
+      Choice(branches) => {
+        let members = branches.into_iter()
+          .map(|branch| self.expr(branch).unwrap_or(TreeSitter::blank()))
+          .collect();
+        Some(TreeSitter::node("CHOICE", "members", Array(members)))
+      }
+      ZeroOrMore(child) => self.expr(child).map(|child| TreeSitter::node("REPEAT", "content", child)),
+      OneOrMore(child) => self.expr(child).map(|child| TreeSitter::node("REPEAT1", "content", child)),
+      ZeroOrOne(child) => self.expr(child).map(|child|
+        TreeSitter::node("CHOICE", "members", Array(vec![child, TreeSitter::blank()]))),
+      NotPredicate(_)
+    | AndPredicate(_) => {
+        self.warn(expr_idx, "The syntactic predicate cannot be exported to tree-sitter and is ignored.");
+        None
+      }
+      SemanticPredicate(child, _) => {
+        self.warn(expr_idx, "The semantic predicate cannot be exported to tree-sitter, only the expression it checks is exported.");
+        self.expr(child)
+      }
+      Cut => {
+        self.warn(expr_idx, "The cut cannot be exported to tree-sitter and is ignored.");
+        None
+      }
+      SemanticAction(child, _)
+    | TypeAscription(child, _)
+    | SpannedExpr(child) => self.expr(child)
+    }
+  }
+
+  fn node(ty: &str, field: &str, value: Json) -> Json {
+    Json::object(vec![("type", Json::str(ty)), (field, value)])
+  }
+
+  fn blank() -> Json {
+    Json::object(vec![("type", Json::str("BLANK"))])
+  }
+
+  fn warn(&self, expr_idx: usize, msg: &str) {
+    self.grammar.cx.span_warn(self.grammar[expr_idx].span(), msg);
+  }
+}
@@ -0,0 +1,159 @@
+// Copyright 2017 Pierre Talbot (IRCAM)
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Computes the FIRST set of the expressions: the characters that can start the input matched by an expression.
+//!
+//! The FIRST set of an expression is only known when the expression fails on the first character if this character is not in the set, in which case we also record the expectations it would report. This is the case of literals, character classes, `.`, and of the sequences, choices, repetitions `e+` and rule calls built from them. The choice compiler uses these sets to skip the branches that cannot match the next character, without changing the error messages (see `back::compiler::choice`). The expressions that can succeed without consuming input (`e*`, `e?`, predicates, ...) do not have a FIRST set.
+
+use middle::typing::ast::*;
+use std::char;
+use std::collections::HashMap;
+
+#[derive(Clone, Debug)]
+pub struct FirstSet
+{
+  /// Sorted and disjoint intervals of characters.
+  pub chars: Vec<CharacterInterval>,
+  /// Expectations recorded by the expression when the first character is not in `chars`.
+  pub expectations: Vec<String>
+}
+
+impl FirstSet
+{
+  fn new(intervals: Vec<CharacterInterval>, expectations: Vec<String>) -> FirstSet {
+    FirstSet {
+      chars: normalize(intervals),
+      expectations: expectations
+    }
+  }
+
+  fn union(self, other: FirstSet) -> FirstSet {
+    let mut chars = self.chars;
+    chars.extend(other.chars.into_iter());
+    let mut expectations = self.expectations;
+    expectations.extend(other.expectations.into_iter());
+    FirstSet::new(chars, expectations)
+  }
+
+  /// Every character can start the expression, the FIRST set does not help to skip it.
+  pub fn is_full(&self) -> bool {
+    self.chars.len() == 1 && self.chars[0].lo == '\0' && self.chars[0].hi == char::MAX
+  }
+}
+
+fn normalize(mut intervals: Vec<CharacterInterval>) -> Vec<CharacterInterval> {
+  intervals.sort_by_key(|i| i.lo);
+  let mut merged: Vec<CharacterInterval> = vec![];
+  for interval in intervals {
+    if let Some(last) = merged.last_mut() {
+      if interval.lo as u32 <= last.hi as u32 + 1 {
+        if interval.hi > last.hi {
+          last.hi = interval.hi;
+        }
+        continue;
+      }
+    }
+    merged.push(interval);
+  }
+  merged
+}
+
+pub fn compute_first_sets<'a, 'b>(mut grammar: TGrammar<'a, 'b>) -> Partial<TGrammar<'a, 'b>> {
+  let first_sets = {
+    let mut analyser = FirstSets {
+      grammar: &grammar,
+      first_sets: HashMap::new(),
+      visiting: vec![]
+    };
+    for expr_idx in 0..grammar.exprs.len() {
+      analyser.first_set(expr_idx);
+    }
+    analyser.first_sets.into_iter()
+      .filter_map(|(expr_idx, first)| first.map(|first| (expr_idx, first)))
+      .collect()
+  };
+  grammar.first_sets = first_sets;
+  Partial::Value(grammar)
+}
+
+struct FirstSets<'a: 'c, 'b: 'a, 'c>
+{
+  grammar: &'c TGrammar<'a, 'b>,
+  first_sets: HashMap<usize, Option<FirstSet>>,
+  /// Rules being visited, a left-recursive call does not have a FIRST set (left recursion is rejected by the analysis anyway).
+  visiting: Vec<Ident>
+}
+
+impl<'a, 'b, 'c> FirstSets<'a, 'b, 'c>
+{
+  fn first_set(&mut self, expr_idx: usize) -> Option<FirstSet> {
+    if let Some(first) = self.first_sets.get(&expr_idx) {
+      return first.clone();
+    }
+    let first = self.compute(expr_idx);
+    self.first_sets.insert(expr_idx, first.clone());
+    first
+  }
+
+  fn compute(&mut self, expr_idx: usize) -> Option<FirstSet> {
+    match self.grammar.expr_by_index(expr_idx) {
+      StrLiteral(lit) => lit.chars().next().map(|c|
+        FirstSet::new(vec![CharacterInterval::new(c, c)], vec![lit.clone()])),
+      CharacterClass(class) => Some(FirstSet::new(class.intervals.clone(), vec![format!("{}", class)])),
+      AnySingleChar => Some(FirstSet::new(
+        vec![CharacterInterval::new('\0', char::MAX)], vec![format!("<character>")])),
+      NonTerminalSymbol(rule) => self.rule_first_set(rule),
+      Sequence(seq) => seq.first().and_then(|&first| self.first_set(first)),
+      Choice(branches) => {
+        let mut first: Option<FirstSet> = None;
+        for branch in branches {
+          first = match (first, self.first_set(branch)) {
+            (_, None) => return None,
+            (None, branch_first) => branch_first,
+            (Some(first), Some(branch_first)) => Some(first.union(branch_first))
+          };
+        }
+        first
+      }
+      OneOrMore(child)
+    | SemanticAction(child, _)
+    | SemanticPredicate(child, _)
+    | TypeAscription(child, _)
+    | SpannedExpr(child) => self.first_set(child),
+      ZeroOrMore(_)
+    | ZeroOrOne(_)
+    | NotPredicate(_)
+    | AndPredicate(_)
+    | Cut => None
+    }
+  }
+
+  /// A rule annotated with `#[label]` reports its label instead of the expectations of its body.
+  fn rule_first_set(&mut self, rule: Ident) -> Option<FirstSet> {
+    if self.visiting.contains(&rule) {
+      return None;
+    }
+    self.visiting.push(rule);
+    let body = self.grammar.expr_index_of_rule(rule);
+    let first = self.first_set(body);
+    self.visiting.pop();
+    match self.grammar.rule_attributes(rule).label {
+      None => first,
+      Some(label) => first.map(|first| FirstSet {
+        chars: first.chars,
+        expectations: vec![label]
+      })
+    }
+  }
+}
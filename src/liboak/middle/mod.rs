@@ -14,7 +14,7 @@
 
 //! This module performs analysis on the PEG and gives a type to each expressions in the AST.
 
-//! The `analysis` module performs some verifications on the grammar description and the `typing` module gives a type to each rule and expression.
+//! The `analysis` module performs some verifications on the grammar description, the `export` module writes the grammar in other formats, the `typing` module gives a type to each rule and expression, the `simplify` module inlines the small rules and the `first_set` module computes the characters that can start each expression.
 
 use rust;
 use middle::typing::ast::*;
@@ -24,13 +24,20 @@ pub use front::ast::FGrammar;
 
 pub mod analysis;
 pub mod typing;
+pub mod export;
+pub mod simplify;
+pub mod first_set;
 
 pub fn typecheck<'a, 'b>(cx: &'a ExtCtxt<'b>, fgrammar: FGrammar) -> Partial<TGrammar<'a, 'b>> {
   Partial::Value(fgrammar)
     .and_then(|grammar| at_least_one_rule_declared(cx, grammar))
     .and_then(|grammar| analysis::analyse(cx, grammar))
+    .and_then(|grammar| export::export_grammar(grammar))
     .and_then(|grammar| extract_stream_type(grammar))
     .and_then(|grammar| typing::type_inference(grammar))
+    .and_then(|(grammar, rec_rules, rec_set)| export::export_typed_grammar(grammar, rec_rules, rec_set))
+    .and_then(|grammar| simplify::simplify_grammar(grammar))
+    .and_then(|grammar| first_set::compute_first_sets(grammar))
 }
 
 fn at_least_one_rule_declared(cx: &ExtCtxt, fgrammar: FGrammar) -> Partial<FGrammar> {
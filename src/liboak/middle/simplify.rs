@@ -0,0 +1,299 @@
+// Copyright 2017 Pierre Talbot (IRCAM)
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Simplifies the typed grammar before the code generation: the calls to small non-recursive rules are replaced by the bodies of these rules, the nested sequences and choices are flattened, the negative predicates followed by `.` or a class are rewritten into a class and the adjacent literals of a sequence are merged.
+//!
+//! The size of a rule is the number of expressions in its body and the rules are inlined if their size is smaller or equal to `#![inline_threshold = 8]`. The inlining is disabled by default, or with a threshold of `0`. Since a merged literal fails at its first character with the merged literal as expectation, the literals are only merged when the inlining is enabled. The rules with a label, with Rust attributes (such as `#[cold]`) or containing a cut are never inlined. The generated AST types, printers and generators of random inputs follow the calls to the rules, so the inlining is disabled when one of them is generated.
+
+use middle::typing::ast::*;
+use rule_graph::*;
+use std::char;
+use std::collections::HashSet;
+
+pub fn simplify_grammar<'a, 'b>(mut grammar: TGrammar<'a, 'b>) -> Partial<TGrammar<'a, 'b>> {
+  let inlined = inlinable_rules(&grammar);
+  inline_calls(&mut grammar, &inlined);
+  for expr_idx in 0..grammar.exprs.len() {
+    simplify_expr(&mut grammar, expr_idx);
+  }
+  Partial::Value(grammar)
+}
+
+fn is_inlining_enabled(grammar: &TGrammar) -> bool {
+  grammar.attributes.inline_threshold > 0
+  && !grammar.attributes.generate_ast
+  && !grammar.attributes.generate_printer
+  && !grammar.attributes.generator
+}
+
+fn inlinable_rules(grammar: &TGrammar) -> HashSet<Ident> {
+  let threshold = grammar.attributes.inline_threshold;
+  if !is_inlining_enabled(grammar) {
+    return HashSet::new();
+  }
+  let rule_graph = RuleGraph::new(grammar);
+  grammar.rules.iter()
+    .map(|rule| rule.ident())
+    .filter(|&rule| {
+      let attributes = grammar.rule_attributes(rule);
+      attributes.label.is_none()
+      && attributes.rust_attributes.iter().all(|attr| attr.is_sugared_doc)
+    })
+    .filter(|&rule| !rule_graph.reachable_from(rule_graph.callees(rule)).contains(&rule))
+    .filter(|&rule| {
+      let body = grammar.expr_index_of_rule(rule);
+      !contains_cut(grammar, body) && size(grammar, body) <= threshold
+    })
+    .collect()
+}
+
+/// The expression of a call is replaced by the root of the body of the rule, the sub-expressions of the body are shared by all the call sites. The root of a body can itself be a call to an inlined rule, which terminates since these rules are not recursive.
+fn inline_calls(grammar: &mut TGrammar, inlined: &HashSet<Ident>) {
+  for expr_idx in 0..grammar.exprs.len() {
+    while let NonTerminalSymbol(rule) = grammar.expr_by_index(expr_idx) {
+      if !inlined.contains(&rule) {
+        break;
+      }
+      let body = grammar.expr_index_of_rule(rule);
+      let body_expr = grammar.exprs[body].clone();
+      let body_ty = grammar.exprs_info[body].ty.clone();
+      grammar.exprs[expr_idx] = body_expr;
+      grammar.exprs_info[expr_idx].ty = body_ty;
+    }
+  }
+}
+
+fn simplify_expr(grammar: &mut TGrammar, expr_idx: usize) {
+  match grammar.expr_by_index(expr_idx) {
+    Sequence(children) => {
+      let children = flatten_sequence(grammar, children);
+      let children = negated_classes(grammar, children);
+      let children =
+        if is_inlining_enabled(grammar) { merge_literals(grammar, children) }
+        else { children };
+      match single_child_type(grammar, expr_idx, &children) {
+        Some(ty) => {
+          let child_expr = grammar.exprs[children[0]].clone();
+          grammar.exprs[expr_idx] = child_expr;
+          grammar.exprs_info[expr_idx].ty = ty;
+        }
+        None => grammar.exprs[expr_idx] = Sequence(children)
+      }
+    }
+    Choice(children) => {
+      let ty = grammar[expr_idx].ty.clone();
+      let children = flatten_choice(grammar, &ty, children);
+      grammar.exprs[expr_idx] = Choice(children);
+    }
+    _ => ()
+  }
+}
+
+/// `e1 (e2 e3)` is equivalent to `e1 e2 e3`, and the values of the inner sequence are already flattened in the tuple type of the outer one. A sequence containing a cut is kept since the cut belongs to the sequence directly in the branch of a choice.
+fn flatten_sequence(grammar: &TGrammar, children: Vec<usize>) -> Vec<usize> {
+  let mut flattened = vec![];
+  for child in children {
+    match grammar.expr_by_index(child) {
+      Sequence(grandchildren) if !contains_cut(grammar, child) => {
+        flattened.extend(flatten_sequence(grammar, grandchildren).into_iter());
+      }
+      _ => flattened.push(child)
+    }
+  }
+  flattened
+}
+
+/// `e1 / (e2 / e3)` is equivalent to `e1 / e2 / e3` if both choices have the same type. A choice containing a cut is kept since the cut only prevents backtracking to the branches of its own choice.
+fn flatten_choice(grammar: &TGrammar, ty: &Type, children: Vec<usize>) -> Vec<usize> {
+  let mut flattened = vec![];
+  for child in children {
+    match grammar.expr_by_index(child) {
+      Choice(branches) if &grammar[child].ty == ty && !contains_cut(grammar, child) => {
+        flattened.extend(flatten_choice(grammar, ty, branches).into_iter());
+      }
+      _ => flattened.push(child)
+    }
+  }
+  flattened
+}
+
+/// `"a" "b"` is merged into `"ab"`, it fails with the expectation `"ab"` instead of `"b"` if only `"a"` matches. The merged literal is a new expression since the literals can be shared by other expressions.
+fn merge_literals(grammar: &mut TGrammar, children: Vec<usize>) -> Vec<usize> {
+  let mut merged: Vec<usize> = vec![];
+  for child in children {
+    let previous = merged.last().cloned();
+    let previous_expr = previous.map(|p| grammar.expr_by_index(p));
+    match (previous_expr, grammar.expr_by_index(child)) {
+      (Some(StrLiteral(first)), StrLiteral(second)) => {
+        let span = grammar[previous.unwrap()].span;
+        grammar.exprs.push(StrLiteral(first + &second));
+        grammar.exprs_info.push(ExprType::new(span, Type::Unit));
+        *merged.last_mut().unwrap() = grammar.exprs.len() - 1;
+      }
+      _ => merged.push(child)
+    }
+  }
+  merged
+}
+
+/// `!t .` and `!t ["c"]`, where `t` only matches single characters, are replaced by the class of the characters matched by `.` or `["c"]` but not by `t`. On failure, this class is reported as expected instead of the expectations of `t` and `.`.
+fn negated_classes(grammar: &mut TGrammar, children: Vec<usize>) -> Vec<usize> {
+  let mut rewritten: Vec<usize> = vec![];
+  for child in children {
+    let excluded = rewritten.last()
+      .and_then(|&previous| match grammar.expr_by_index(previous) {
+        NotPredicate(predicate) => single_chars(grammar, predicate),
+        _ => None
+      });
+    let class = match grammar.expr_by_index(child) {
+      AnySingleChar => Some(vec![CharacterInterval::new('\0', char::MAX)]),
+      CharacterClass(class) => Some(class.intervals),
+      _ => None
+    };
+    match (excluded, class) {
+      (Some(excluded), Some(class)) => {
+        let intervals = difference(class, excluded);
+        if intervals.is_empty() {
+          rewritten.push(child);
+        }
+        else {
+          let span = grammar[child].span;
+          let ty = grammar[child].ty.clone();
+          grammar.exprs.push(CharacterClass(CharacterClassExpr::new(intervals)));
+          grammar.exprs_info.push(ExprType::new(span, ty));
+          *rewritten.last_mut().unwrap() = grammar.exprs.len() - 1;
+        }
+      }
+      _ => rewritten.push(child)
+    }
+  }
+  rewritten
+}
+
+/// The characters matched by `expr` if it only matches single characters: the literals of one character, the classes and the choices of them.
+fn single_chars(grammar: &TGrammar, expr: usize) -> Option<Vec<CharacterInterval>> {
+  match grammar.expr_by_index(expr) {
+    StrLiteral(ref lit) if lit.chars().count() == 1 => {
+      let c = lit.chars().next().unwrap();
+      Some(vec![CharacterInterval::new(c, c)])
+    }
+    CharacterClass(class) => Some(class.intervals),
+    Choice(branches) => {
+      let mut chars = vec![];
+      for branch in branches {
+        match single_chars(grammar, branch) {
+          Some(branch_chars) => chars.extend(branch_chars.into_iter()),
+          None => return None
+        }
+      }
+      Some(chars)
+    }
+    TypeAscription(child, _) => single_chars(grammar, child),
+    _ => None
+  }
+}
+
+/// Characters of the intervals `class` which are not in `excluded`.
+fn difference(class: Vec<CharacterInterval>, excluded: Vec<CharacterInterval>) -> Vec<CharacterInterval> {
+  let mut excluded: Vec<(u32, u32)> = excluded.into_iter()
+    .map(|interval| (interval.lo as u32, interval.hi as u32))
+    .collect();
+  excluded.sort();
+  let mut intervals = vec![];
+  for interval in class {
+    let mut lo = interval.lo as u32;
+    let hi = interval.hi as u32;
+    for &(ex_lo, ex_hi) in &excluded {
+      if lo > hi {
+        break;
+      }
+      if ex_hi < lo || ex_lo > hi {
+        continue;
+      }
+      if ex_lo > lo {
+        push_interval(&mut intervals, lo, ex_lo - 1);
+      }
+      lo = ex_hi + 1;
+    }
+    if lo <= hi {
+      push_interval(&mut intervals, lo, hi);
+    }
+  }
+  intervals
+}
+
+/// Pushes the characters between `lo` and `hi`, the surrogate code points are not characters and they split the interval.
+fn push_interval(intervals: &mut Vec<CharacterInterval>, lo: u32, hi: u32) {
+  let (surrogate_lo, surrogate_hi) = (0xD800, 0xDFFF);
+  if lo < surrogate_lo && hi > surrogate_hi {
+    push_interval(intervals, lo, surrogate_lo - 1);
+    push_interval(intervals, surrogate_hi + 1, hi);
+  }
+  else {
+    let lo = if lo >= surrogate_lo && lo <= surrogate_hi { surrogate_hi + 1 } else { lo };
+    let hi = if hi >= surrogate_lo && hi <= surrogate_hi { surrogate_lo - 1 } else { hi };
+    if lo <= hi {
+      intervals.push(CharacterInterval::new(
+        char::from_u32(lo).unwrap(), char::from_u32(hi).unwrap()));
+    }
+  }
+}
+
+/// A sequence with a single child is replaced by its child, such as `(!"a" .)*` which becomes a repetition of a class. It is only done if the child produces the value of the sequence, we return the type of the child in this case.
+fn single_child_type(grammar: &TGrammar, seq: usize, children: &Vec<usize>) -> Option<Type> {
+  if children.len() != 1 {
+    return None;
+  }
+  match (grammar[seq].ty.clone(), grammar[children[0]].ty.clone()) {
+    (Type::Unit, _) => Some(Type::Unit),
+    (_, Type::Atom) if grammar[seq].type_cardinality() == 1 => Some(Type::Atom),
+    _ => None
+  }
+}
+
+/// Number of expressions in the tree rooted at `expr_idx`, a call counts for one expression.
+fn size(grammar: &TGrammar, expr_idx: usize) -> usize {
+  1 + children(grammar, expr_idx).into_iter()
+    .map(|child| size(grammar, child))
+    .sum::<usize>()
+}
+
+fn contains_cut(grammar: &TGrammar, expr_idx: usize) -> bool {
+  match grammar.expr_by_index(expr_idx) {
+    Cut => true,
+    _ => children(grammar, expr_idx).into_iter()
+      .any(|child| contains_cut(grammar, child))
+  }
+}
+
+fn children(grammar: &TGrammar, expr_idx: usize) -> Vec<usize> {
+  match grammar.expr_by_index(expr_idx) {
+      Sequence(children)
+    | Choice(children) => children,
+      ZeroOrMore(child)
+    | OneOrMore(child)
+    | ZeroOrOne(child)
+    | NotPredicate(child)
+    | AndPredicate(child)
+    | SemanticAction(child, _)
+    | SemanticPredicate(child, _)
+    | TypeAscription(child, _)
+    | SpannedExpr(child) => vec![child],
+      StrLiteral(_)
+    | AnySingleChar
+    | CharacterClass(_)
+    | NonTerminalSymbol(_)
+    | Cut => vec![]
+  }
+}
@@ -40,7 +40,8 @@ impl<'a, 'b> IGrammar<'a, 'b>
       stream_alias: agrammar.stream_alias,
       rust_functions: agrammar.rust_functions,
       rust_items: agrammar.rust_items,
-      attributes: agrammar.attributes
+      attributes: agrammar.attributes,
+      first_sets: agrammar.first_sets
     };
     grammar.exprs_info = exprs_info.into_iter()
       .map(|e| ExprIType::infer(e.span))
@@ -49,13 +50,16 @@ impl<'a, 'b> IGrammar<'a, 'b>
     grammar
   }
 
-  pub fn action_type(&self, expr_idx: usize, action: Ident) -> IType
+  pub fn action_type(&self, expr_idx: usize, action: RustAction) -> IType
   {
-    match self.rust_functions[&action].node {
-      rust::ItemKind::Fn(ref decl,..) => {
-        Regular(Action(decl.output.clone()))
-      },
-      _ => {
+    match self.action_return_type(&action) {
+      Some(ret_ty) => {
+        match result_ok_type(&ret_ty) {
+          Some(ok_ty) => Regular(Action(rust::FunctionRetTy::Ty(ok_ty))),
+          None => Regular(Action(ret_ty))
+        }
+      }
+      None => {
         self.span_err(self[expr_idx].span, format!(
           "Only function items are currently allowed in semantic actions."));
         Regular(Unit)
@@ -77,7 +81,8 @@ impl<'a, 'b> IGrammar<'a, 'b>
       stream_alias: self.stream_alias,
       rust_functions: self.rust_functions,
       rust_items: self.rust_items,
-      attributes: self.attributes
+      attributes: self.attributes,
+      first_sets: self.first_sets
     }
   }
 
@@ -97,6 +102,13 @@ impl<'a, 'b> IGrammar<'a, 'b>
   }
 }
 
+impl<'a, 'b> TGrammar<'a, 'b>
+{
+  pub fn display_type(&self, expr_idx: usize) -> String {
+    self[expr_idx].ty.display_with(&|child| self.display_type(child))
+  }
+}
+
 pub type ExprIType = ExpressionInfo<IType>;
 pub type ExprType = ExpressionInfo<Type>;
 
@@ -289,7 +301,11 @@ pub enum Type
   /// `Tuple(vec![i,..,j])` is a tuple with the types of the sub-expressions at index `{i,..,j}`.
   /// Precondition: Tuple size >= 2.
   Tuple(Vec<usize>),
-  Action(rust::FunctionRetTy)
+  Action(rust::FunctionRetTy),
+  /// Text matched by an expression copied in a `String` (`e -> String`).
+  Text,
+  /// Slice of the stream matched by an expression (`e -> &str`), see `Grammar::slice_type`.
+  Slice
 }
 
 impl Type
@@ -300,7 +316,9 @@ impl Type
       Atom
     | Optional(_)
     | List(_)
-    | Action(_) => 1,
+    | Action(_)
+    | Text
+    | Slice => 1,
     // | Spanned(_) => 1,
       Tuple(ref indexes) => indexes.len()
     }
@@ -332,24 +350,33 @@ impl Type
         }
       }
       (Action(_), Action(_)) => true,
+      (Text, Text) => true,
+      (Slice, Slice) => true,
       _ => false
     }
   }
 
   pub fn display(&self, grammar: &IGrammar) -> String {
+    self.display_with(&|child| grammar.type_of(child).display(grammar))
+  }
+
+  /// `display_child` displays the type of the sub-expression at the given index.
+  pub fn display_with(&self, display_child: &Fn(usize) -> String) -> String {
     match self.clone() {
       Unit => format!("()"),
       Atom => format!("char"),
+      Text => format!("String"),
+      Slice => format!("&str"),
       Optional(child) =>
-        format!("Option<{}>", grammar.type_of(child).display(grammar)),
+        format!("Option<{}>", display_child(child)),
       List(child) =>
-        format!("Vec<{}>", grammar.type_of(child).display(grammar)),
+        format!("Vec<{}>", display_child(child)),
       // Spanned(child) =>
       //   format!("<todo>"), //(<Range<Stream> as StreamSpan>::Output, {})", grammar.type_of(child).display(grammar))
       Tuple(children) => {
         let mut display = format!("(");
         for child in children {
-          display.extend(grammar.type_of(child).display(grammar).chars());
+          display.extend(display_child(child).chars());
           display.push_str(", ");
         }
         display.pop();
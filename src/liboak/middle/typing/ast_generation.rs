@@ -0,0 +1,205 @@
+// Copyright 2017 Pierre Talbot (IRCAM)
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Generation of the Rust types of the rules when the grammar is annotated with `#![generate_ast]`.
+//!
+//! Before the type inference, the body of every rule without semantic action and without type ascription is wrapped in a call to a constructor, such as `make_sum`, whose return type is the struct `Sum`. If the rule is a choice, each branch is wrapped in the constructor of one variant of the enum `Sum`. The constructor actions carry the type they build so the typing knows the type of the rule; the constructors and the types are generated in `back::compiler::ast_type` once the types of the fields are inferred.
+//!
+//! Rules of invisible type, such as the rules matching keywords or spaces, do not get a type.
+
+use middle::analysis::ast::*;
+use ast::Expression::*;
+use std::collections::{HashMap, HashSet};
+
+pub fn generate_ast<'a, 'b>(mut grammar: AGrammar<'a, 'b>) -> AGrammar<'a, 'b> {
+  if !grammar.attributes.generate_ast {
+    return grammar;
+  }
+  let candidates = candidate_rules(&grammar);
+  let generated: Vec<Rule> = {
+    let mut invisibility = Invisibility::new(&grammar, &candidates);
+    grammar.rules.iter().cloned()
+      .filter(|rule| candidates.contains(&rule.ident()))
+      .filter(|rule| !invisibility.is_invisible_rule(rule.ident()))
+      .collect()
+  };
+  for rule in generated {
+    let ast_type = wrap_in_constructors(&mut grammar, rule);
+    grammar.attributes.rules.entry(rule.ident())
+      .or_insert_with(RuleAttributes::default)
+      .ast_type = Some(ast_type);
+  }
+  grammar
+}
+
+/// Rules that do not already have a type given by the user: with a semantic action, a type ascription or a type declaration. We also keep the rules whose type name is already declared in the grammar.
+fn candidate_rules(grammar: &AGrammar) -> HashSet<Ident> {
+  let declared_types: HashSet<String> = grammar.rust_items.iter()
+    .map(|item| ident_to_string(item.ident()))
+    .collect();
+  grammar.rules.iter()
+    .filter(|rule| {
+      match grammar.expr_by_index(rule.expr_idx) {
+        SemanticAction(..) | TypeAscription(..) => false,
+        _ => true
+      }
+    })
+    .filter(|rule| grammar.rule_attributes(rule.ident()).declared_type.is_none())
+    .filter(|rule| !declared_types.contains(&type_name(rule.ident())))
+    .map(|rule| rule.ident())
+    .collect()
+}
+
+fn type_name(rule: Ident) -> String {
+  to_camel_case(ident_to_string(rule).as_str())
+}
+
+fn wrap_in_constructors<'a, 'b>(grammar: &mut AGrammar<'a, 'b>, rule: Rule) -> AstType {
+  let name = string_to_ident(grammar.cx, type_name(rule.ident()));
+  match grammar.expr_by_index(rule.expr_idx) {
+    Choice(branches) => {
+      let mut variants = vec![];
+      let mut names = HashSet::new();
+      for (i, branch) in branches.into_iter().enumerate() {
+        let variant = variant_name(grammar, branch, i, &mut names);
+        let constructor = string_to_ident(grammar.cx,
+          format!("make_{}_{}", rule.ident(), ident_to_string(variant).to_lowercase()));
+        wrap_in_constructor(grammar, branch, name, constructor);
+        variants.push((variant, constructor));
+      }
+      AstType::Enum { name: name, variants: variants }
+    }
+    _ => {
+      let constructor = string_to_ident(grammar.cx, format!("make_{}", rule.ident()));
+      wrap_in_constructor(grammar, rule.expr_idx, name, constructor);
+      AstType::Struct { name: name, constructor: constructor }
+    }
+  }
+}
+
+/// A branch calling a single rule `r` is named after `r`, a branch starting with a keyword such as `"let"` is named after it, the other branches are named after their position.
+fn variant_name(grammar: &AGrammar, branch: usize, position: usize, names: &mut HashSet<String>) -> Ident {
+  let first = match grammar.expr_by_index(branch) {
+    Sequence(seq) => grammar.expr_by_index(seq[0]),
+    expr => expr
+  };
+  let name = match (grammar.expr_by_index(branch), first) {
+    (NonTerminalSymbol(rule), _) => to_camel_case(ident_to_string(rule).as_str()),
+    (_, StrLiteral(ref lit)) if !lit.is_empty() && lit.chars().all(|c| c.is_alphabetic() || c == '_') =>
+      to_camel_case(lit.as_str()),
+    _ => format!("Branch{}", position)
+  };
+  let mut unique_name = name.clone();
+  let mut suffix = 1;
+  while !names.insert(unique_name.clone()) {
+    suffix += 1;
+    unique_name = format!("{}{}", name, suffix);
+  }
+  string_to_ident(grammar.cx, unique_name)
+}
+
+/// Replace the expression at `expr_idx` by `expr > constructor`, the action builds a value of type `ty`.
+fn wrap_in_constructor<'a, 'b>(grammar: &mut AGrammar<'a, 'b>, expr_idx: usize, ty: Ident, constructor: Ident) {
+  let span = grammar[expr_idx].span();
+  let expr = grammar.exprs[expr_idx].clone();
+  let copy_idx = grammar.alloc_expr(span, expr);
+  grammar.exprs[expr_idx] = SemanticAction(copy_idx, RustAction::Constructor(constructor, ty));
+}
+
+/// Approximates the invisible type inferred by `typing::surface`. A call to a rule with a generated type is never invisible, and a rule only reachable through a cycle of invisible expressions is invisible (the recursive type is reduced to `(^)`).
+struct Invisibility<'a: 'c, 'b: 'a, 'c>
+{
+  grammar: &'c AGrammar<'a, 'b>,
+  candidates: &'c HashSet<Ident>,
+  visiting: HashSet<Ident>,
+  rules: HashMap<Ident, bool>
+}
+
+impl<'a, 'b, 'c> Invisibility<'a, 'b, 'c>
+{
+  fn new(grammar: &'c AGrammar<'a, 'b>, candidates: &'c HashSet<Ident>) -> Self {
+    Invisibility {
+      grammar: grammar,
+      candidates: candidates,
+      visiting: HashSet::new(),
+      rules: HashMap::new()
+    }
+  }
+
+  fn is_invisible_rule(&mut self, rule: Ident) -> bool {
+    if let Some(invisible) = self.rules.get(&rule) {
+      return *invisible;
+    }
+    if !self.visiting.insert(rule) {
+      return true;
+    }
+    let expr_idx = self.grammar.expr_index_of_rule(rule);
+    let invisible = self.visit_expr(expr_idx);
+    self.visiting.remove(&rule);
+    self.rules.insert(rule, invisible);
+    invisible
+  }
+}
+
+impl<'a, 'b, 'c> ExprByIndex for Invisibility<'a, 'b, 'c>
+{
+  fn expr_by_index(&self, index: usize) -> Expression {
+    self.grammar.expr_by_index(index)
+  }
+}
+
+impl<'a, 'b, 'c> Visitor<bool> for Invisibility<'a, 'b, 'c>
+{
+  fn visit_str_literal(&mut self, _this: usize, _lit: String) -> bool {
+    true
+  }
+
+  fn visit_atom(&mut self, _this: usize) -> bool {
+    false
+  }
+
+  fn visit_non_terminal_symbol(&mut self, _this: usize, rule: Ident) -> bool {
+    // A recursive call to a rule with a generated type is boxed instead of being reduced to `(^)`.
+    if self.candidates.contains(&rule) && self.visiting.contains(&rule) {
+      false
+    }
+    else {
+      self.is_invisible_rule(rule)
+    }
+  }
+
+  fn visit_sequence(&mut self, _this: usize, children: Vec<usize>) -> bool {
+    walk_exprs(self, children).into_iter().all(|invisible| invisible)
+  }
+
+  fn visit_choice(&mut self, _this: usize, children: Vec<usize>) -> bool {
+    walk_exprs(self, children).into_iter().all(|invisible| invisible)
+  }
+
+  fn visit_syntactic_predicate(&mut self, _this: usize, _child: usize) -> bool {
+    true
+  }
+
+  fn visit_semantic_action(&mut self, _this: usize, _child: usize, _action: RustAction) -> bool {
+    false
+  }
+
+  fn visit_spanned_expr(&mut self, _this: usize, _child: usize) -> bool {
+    false
+  }
+
+  fn visit_type_ascription(&mut self, _this: usize, _child: usize, ty: IType) -> bool {
+    ty == IType::Invisible
+  }
+}
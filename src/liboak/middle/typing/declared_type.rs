@@ -0,0 +1,115 @@
+// Copyright 2017 Pierre Talbot (IRCAM)
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Check the types declared by the user with `rule: Type = e` against the inferred types. The declared type is compared structurally with the inferred type: `char`, `String`, `Option<T>`, `Vec<T>` and tuples are matched against the types of the parsing expressions (a path such as `std::vec::Vec<T>` is matched by its last segment), and the types returned by the semantic actions are compared with the declared type once both are printed.
+
+use middle::typing::ast::*;
+use middle::typing::ast::IType::*;
+use middle::typing::ast::Type::*;
+use rust;
+use rust::TyKind;
+
+/// Returns `false` if a declared type does not match the inferred type.
+pub fn check_declared_types(grammar: &IGrammar) -> bool {
+  let mut valid = true;
+  for rule in &grammar.rules {
+    if let Some(declared) = grammar.rule_attributes(rule.ident()).declared_type {
+      if !matches_type(grammar, &declared, grammar.type_of(rule.expr_idx)) {
+        grammar.span_err(rule.span(), format!(
+          "The type declared for the rule `{}` is `{}` but the type inferred is `{}`.",
+          rule.ident(), rust::ty_to_string(&*declared), inferred_type(grammar, rule.expr_idx)));
+        valid = false;
+      }
+    }
+  }
+  valid
+}
+
+/// Invisible types are reduced to the unit type in the generated code.
+fn inferred_type(grammar: &IGrammar, expr_idx: usize) -> String {
+  match grammar.type_of(expr_idx) {
+    Invisible => format!("()"),
+    ty => ty.display(grammar)
+  }
+}
+
+fn matches_type(grammar: &IGrammar, declared: &RTy, inferred: IType) -> bool {
+  match inferred {
+    Invisible
+  | Regular(Unit) => is_unit(declared),
+    Regular(Atom) => path_args(declared, "char").map_or(false, |args| args.is_empty()),
+    Regular(Text) => path_args(declared, "String").map_or(false, |args| args.is_empty()),
+    Regular(Slice) => {
+      (grammar.is_runtime_stream() && is_str_ref(declared))
+      || rust::ty_to_string(declared) == rust::ty_to_string(&*grammar.slice_type())
+    }
+    Regular(Optional(child)) => matches_param(grammar, declared, "Option", child),
+    Regular(List(child)) => matches_param(grammar, declared, "Vec", child),
+    Regular(Tuple(children)) => {
+      match &declared.node {
+        &TyKind::Tup(ref tys) => tys.len() == children.len()
+          && tys.iter().zip(children.into_iter())
+               .all(|(ty, child)| matches_type(grammar, ty, grammar.type_of(child))),
+        _ => false
+      }
+    }
+    Regular(Action(rust::FunctionRetTy::Default(_))) => is_unit(declared),
+    Regular(Action(rust::FunctionRetTy::Ty(ty))) =>
+      rust::ty_to_string(declared) == rust::ty_to_string(&*ty),
+    Infer
+  | Rec(_) => false
+  }
+}
+
+fn matches_param(grammar: &IGrammar, declared: &RTy, name: &str, child: usize) -> bool {
+  match path_args(declared, name) {
+    Some(ref args) if args.len() == 1 => matches_type(grammar, &args[0], grammar.type_of(child)),
+    _ => false
+  }
+}
+
+fn is_unit(ty: &RTy) -> bool {
+  match &ty.node {
+    &TyKind::Tup(ref tys) => tys.is_empty(),
+    _ => false
+  }
+}
+
+fn is_str_ref(ty: &RTy) -> bool {
+  match &ty.node {
+    &TyKind::Rptr(_, ref mut_ty) => path_args(&mut_ty.ty, "str").map_or(false, |args| args.is_empty()),
+    _ => false
+  }
+}
+
+/// The type arguments of `ty` if it is a path whose last segment is `name`.
+fn path_args(ty: &RTy, name: &str) -> Option<Vec<RTy>> {
+  match &ty.node {
+    &TyKind::Path(None, ref path) => {
+      path.segments.last().and_then(|segment| {
+        if segment.identifier.name != name {
+          return None;
+        }
+        match segment.parameters {
+          Some(ref params) => match &**params {
+            &rust::PathParameters::AngleBracketed(ref data) => Some(data.types.clone()),
+            _ => None
+          },
+          None => Some(vec![])
+        }
+      })
+    }
+    _ => None
+  }
+}
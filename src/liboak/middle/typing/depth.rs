@@ -18,6 +18,9 @@ use middle::typing::ast::Type::*;
 use middle::typing::ast::IType::*;
 use middle::typing::surface::*;
 use middle::typing::typing_printer::*;
+use middle::typing::declared_type::*;
+use middle::analysis::ast::RuleAttributes;
+use std::collections::HashSet;
 
 pub struct Depth<'a, 'b: 'a>
 {
@@ -28,19 +31,27 @@ pub struct Depth<'a, 'b: 'a>
 
 impl<'a, 'b> Depth<'a, 'b>
 {
-  pub fn infer(grammar: IGrammar<'a, 'b>) -> TGrammar<'a, 'b> {
+  /// Also returns the rules whose type is reduced to `(^)` and the recursive paths responsible for it.
+  pub fn infer(grammar: IGrammar<'a, 'b>) -> Partial<(TGrammar<'a, 'b>, HashSet<Ident>, RecSet)> {
     let mut engine = Depth::new(grammar);
     engine.surface.surface();
-    engine.warn_recursive_type();
+    engine.box_generated_rec_calls();
+    let (rec_rules, rec_set) = engine.warn_recursive_type();
     engine.reduce_all_rec();
     engine.depth();
     engine.reduce_all_invisible();
+    let declared_types = check_declared_types(&engine.surface.grammar);
     let grammar = engine.surface.grammar;
     if grammar.attributes.print_typing.debug() {
       println!("After applying Depth.");
       print_debug(&grammar);
     }
-    grammar.map_exprs_info(engine.exprs_info)
+    if declared_types {
+      Partial::Value((grammar.map_exprs_info(engine.exprs_info), rec_rules, rec_set))
+    }
+    else {
+      Partial::Nothing
+    }
   }
 
   fn new(grammar: IGrammar<'a, 'b>) -> Depth<'a, 'b> {
@@ -83,17 +94,50 @@ impl<'a, 'b> Depth<'a, 'b>
     self.surface.type_of(expr_idx)
   }
 
-  fn warn_recursive_type(&mut self) {
+  /// Visit `expr_idx` without forcing its type to unit, even if its parent is of unit type.
+  fn visit_value_expr(&mut self, expr_idx: usize) {
+    let old = self.under_unit;
+    self.under_unit = false;
+    self.visit_expr(expr_idx);
+    self.under_unit = old;
+  }
+
+  /// Visit `expr_idx` as if its parent was of unit type.
+  fn visit_unit_expr(&mut self, expr_idx: usize) {
+    let old = self.under_unit;
+    self.under_unit = true;
+    self.visit_expr(expr_idx);
+    self.under_unit = old;
+  }
+
+  /// The calls between the rules of a recursive path of generated types are boxed, see `back::compiler::ast_type`.
+  fn box_generated_rec_calls(&mut self) {
+    let rec_set = self.surface.generated_rec_set.clone();
+    for rec_path in rec_set.path_set {
+      for rule in &rec_path.path {
+        self.surface.grammar.attributes.rules.entry(*rule)
+          .or_insert_with(RuleAttributes::default)
+          .boxed_calls.extend(rec_path.path.iter().cloned());
+      }
+    }
+  }
+
+  /// Returns the rules whose type is reduced to `(^)` because it is infinitely recursive, and the recursive paths responsible.
+  fn warn_recursive_type(&mut self) -> (HashSet<Ident>, RecSet) {
     let mut rec_set = RecSet::empty();
+    let mut rec_rules = HashSet::new();
     for rule in self.surface.grammar.rules.clone() {
       if let Rec(r) = self.type_of(rule.expr_idx) {
+        let r = r.remove_unit_kind();
+        if !r.is_empty() {
+          rec_rules.insert(rule.ident());
+        }
         rec_set = rec_set.union(r);
       }
     }
-    rec_set = rec_set.remove_unit_kind();
     if !rec_set.is_empty() {
       let mut errors = vec![];
-      for rec_path in rec_set.path_set {
+      for rec_path in rec_set.path_set.clone() {
         errors.push((
           self.surface.grammar.find_rule_by_ident(rec_path.path[0]).span(),
           format!("Infinite recursive type (type inferred: `(^)`): {}", rec_path.display())
@@ -101,6 +145,7 @@ impl<'a, 'b> Depth<'a, 'b>
       }
       self.surface.grammar.multi_locations_warn(errors);
     }
+    (rec_rules, rec_set)
   }
 }
 
@@ -145,9 +190,14 @@ impl<'a, 'b> Visitor<()> for Depth<'a, 'b>
   unit_visitor_impl!(sequence);
   unit_visitor_impl!(choice);
 
-  fn visit_type_ascription(&mut self, _this: usize, child: usize, _ty: IType) {
+  fn visit_type_ascription(&mut self, _this: usize, child: usize, ty: IType) {
     self.surface_expr(child);
-    self.visit_expr(child);
+    match ty {
+      // The text matched by `child` is extracted from the stream, so it does not build a value.
+      Regular(Text)
+    | Regular(Slice) => self.visit_unit_expr(child),
+      _ => self.visit_expr(child)
+    }
   }
 
   fn visit_syntactic_predicate(&mut self, _this: usize, child: usize) {
@@ -155,8 +205,18 @@ impl<'a, 'b> Visitor<()> for Depth<'a, 'b>
     self.visit_expr(child);
   }
 
-  fn visit_semantic_action(&mut self, _this: usize, child: usize, _action: Ident) {
+  fn visit_semantic_action(&mut self, _this: usize, child: usize, action: RustAction) {
     self.surface_expr(child);
-    self.visit_expr(child);
+    // Fallible actions are called in recognizers too, so their arguments must be built.
+    if self.surface.grammar.is_fallible_action(&action) {
+      self.visit_value_expr(child);
+    }
+    else {
+      self.visit_expr(child);
+    }
+  }
+
+  fn visit_semantic_predicate(&mut self, _this: usize, child: usize, _pred: RExpr) {
+    self.visit_value_expr(child);
   }
 }
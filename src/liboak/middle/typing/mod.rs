@@ -15,14 +15,21 @@
 use middle::analysis::ast::AGrammar;
 use middle::typing::ast::*;
 use middle::typing::depth::*;
+use middle::typing::ast_generation::*;
+use std::collections::HashSet;
 
 pub mod ast;
 mod surface;
 mod depth;
 mod type_rewriting;
 mod typing_printer;
+mod declared_type;
+mod ast_generation;
 
-pub fn type_inference<'a, 'b>(agrammar: AGrammar<'a, 'b>) -> Partial<TGrammar<'a, 'b>> {
-  let grammar = IGrammar::from(agrammar);
-  Partial::Value(Depth::infer(grammar))
+/// Returns the typed grammar with the rules whose type is reduced to `(^)` and the recursive paths responsible for it.
+pub fn type_inference<'a, 'b>(agrammar: AGrammar<'a, 'b>)
+  -> Partial<(TGrammar<'a, 'b>, HashSet<Ident>, RecSet)>
+{
+  let grammar = IGrammar::from(generate_ast(agrammar));
+  Depth::infer(grammar)
 }
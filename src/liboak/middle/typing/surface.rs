@@ -16,10 +16,13 @@ use middle::typing::ast::*;
 use middle::typing::ast::IType::*;
 use middle::typing::type_rewriting::*;
 use middle::typing::typing_printer::*;
+use rust;
 
 pub struct Surface<'a, 'b: 'a>
 {
   pub grammar: IGrammar<'a, 'b>,
+  /// Recursive paths between the rules with a generated type (`#![generate_ast]`).
+  pub generated_rec_set: RecSet,
   recursion_path: Vec<Ident>
 }
 
@@ -28,6 +31,7 @@ impl<'a, 'b> Surface<'a, 'b>
   pub fn new(grammar: IGrammar<'a, 'b>) -> Surface<'a, 'b> {
     Surface {
       grammar: grammar,
+      generated_rec_set: RecSet::empty(),
       recursion_path: vec![]
     }
   }
@@ -50,6 +54,9 @@ impl<'a, 'b> Surface<'a, 'b>
       if self.is_rec(rule) {
         self.infer_rec_type(rule)
       }
+      else if let Some(ast_type) = self.grammar.rule_attributes(rule).ast_type {
+        self.visit_generated_rule(rule, expr_idx, ast_type.name())
+      }
       else {
         self.recursion_path.push(rule);
         let ty = self.visit_expr(expr_idx);
@@ -59,10 +66,25 @@ impl<'a, 'b> Surface<'a, 'b>
       }
     }
     else {
+      // Only the rules with a generated type are typed before their body is visited.
+      if self.is_rec(rule) {
+        let rec_set = RecSet::new(RecPath::new(RecKind::Value, self.rec_path(rule)));
+        self.generated_rec_set = self.generated_rec_set.clone().union(rec_set);
+      }
       rule_ty
     }
   }
 
+  /// The type of a generated rule is known before visiting its body, thus a recursive call to this rule is not reduced to `(^)` but recorded in `generated_rec_set`.
+  fn visit_generated_rule(&mut self, rule: Ident, expr_idx: usize, ty: Ident) -> IType {
+    let rule_ty = Regular(Type::Action(rust::FunctionRetTy::Ty(quote_ty!(self.grammar.cx, $ty))));
+    self.type_expr(expr_idx, rule_ty.clone());
+    self.recursion_path.push(rule);
+    walk_expr(self, expr_idx);
+    self.recursion_path.pop();
+    rule_ty
+  }
+
   fn is_rec(&self, rule: Ident) -> bool {
     self.recursion_path.iter().any(|r| *r == rule)
   }
@@ -77,13 +99,17 @@ impl<'a, 'b> Surface<'a, 'b>
   }
 
   fn infer_rec_type(&mut self, entry_rule: Ident) -> IType {
-    let rec_path = self.recursion_path.clone();
+    IType::rec(RecKind::Unit, self.rec_path(entry_rule))
+  }
+
+  fn rec_path(&self, entry_rule: Ident) -> Vec<Ident> {
     let mut rec_shorter_path = vec![entry_rule];
     rec_shorter_path.extend(
-      rec_path.into_iter()
+      self.recursion_path.iter()
         .rev()
+        .cloned()
         .take_while(|r| *r != entry_rule));
-    IType::rec(RecKind::Unit, rec_shorter_path)
+    rec_shorter_path
   }
 
   fn type_mismatch_branches(&self, rec_set: RecSet, sum_expr: usize, branches: Vec<usize>, tys: Vec<IType>) {
@@ -121,7 +147,11 @@ impl<'a, 'b> Visitor<IType> for Surface<'a, 'b>
     let mut this_ty = self.type_of(this);
     if this_ty == Infer {
       this_ty = walk_expr(self, this);
-      let reduced_ty = TypeRewriting::reduce(&self.grammar, this_ty);
+      let reduced_ty = match self.expr_by_index(this) {
+        // The type of an ascription is given by the user and is never rewritten.
+        TypeAscription(..) => this_ty,
+        _ => TypeRewriting::reduce(&self.grammar, this_ty)
+      };
       self.type_expr(this, reduced_ty)
     }
     else {
@@ -139,7 +169,13 @@ impl<'a, 'b> Visitor<IType> for Surface<'a, 'b>
     IType::Invisible
   }
 
-  fn visit_type_ascription(&mut self, _this: usize, _child: usize, ty: IType) -> IType {
+  fn visit_type_ascription(&mut self, _this: usize, child: usize, ty: IType) -> IType {
+    // `Vec<_>` and `Option<_>` refer to the type of the repeated expression.
+    match ty {
+      Regular(Type::List(_))
+    | Regular(Type::Optional(_)) => { self.visit_expr(child); }
+      _ => ()
+    }
     ty
   }
 
@@ -147,7 +183,11 @@ impl<'a, 'b> Visitor<IType> for Surface<'a, 'b>
     IType::Regular(Type::Atom)
   }
 
-  fn visit_semantic_action(&mut self, this: usize, _child: usize, action: Ident) -> IType {
+  fn visit_semantic_action(&mut self, this: usize, child: usize, action: RustAction) -> IType {
+    // The fields of a generated type are typed while its rule is on the recursion path.
+    if let RustAction::Constructor(..) = action {
+      self.visit_expr(child);
+    }
     self.grammar.action_type(this, action)
   }
 
@@ -167,6 +207,11 @@ impl<'a, 'b> Visitor<IType> for Surface<'a, 'b>
     IType::Regular(Type::Optional(child))
   }
 
+  fn visit_semantic_predicate(&mut self, _this: usize, child: usize, _pred: RExpr) -> IType {
+    self.visit_expr(child);
+    IType::Regular(Type::Tuple(vec![child]))
+  }
+
   fn visit_spanned_expr(&mut self, _this: usize, child: usize) -> IType {
     self.visit_expr(child);
     IType::Regular(Type::Tuple(vec![self.grammar.span_ty_idx(), child]))
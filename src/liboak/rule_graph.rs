@@ -0,0 +1,95 @@
+// Copyright 2017 Pierre Talbot (IRCAM)
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Call graph of the rules: there is an edge from `r1` to `r2` if the rule `r1` calls the rule `r2`.
+
+use ast::*;
+use visitor::*;
+use std::collections::{HashMap, HashSet};
+
+pub struct RuleGraph
+{
+  calls: HashMap<Ident, Vec<Ident>>
+}
+
+impl RuleGraph
+{
+  pub fn new<'a, 'b, ExprInfo>(grammar: &Grammar<'a, 'b, ExprInfo>) -> RuleGraph {
+    let mut calls = HashMap::new();
+    for rule in &grammar.rules {
+      let mut visitor = RuleCalls::new(grammar);
+      visitor.visit_expr(rule.expr_idx);
+      calls.insert(rule.ident(), visitor.callees);
+    }
+    RuleGraph {
+      calls: calls
+    }
+  }
+
+  /// Rules called by `rule` in the order of their first occurrence.
+  pub fn callees(&self, rule: Ident) -> Vec<Ident> {
+    self.calls.get(&rule).cloned().unwrap_or(vec![])
+  }
+
+  /// Rules reachable from `roots`, roots included.
+  pub fn reachable_from<I>(&self, roots: I) -> HashSet<Ident> where
+   I: IntoIterator<Item=Ident>
+  {
+    let mut reachable = HashSet::new();
+    let mut to_visit: Vec<Ident> = roots.into_iter().collect();
+    while let Some(rule) = to_visit.pop() {
+      if reachable.insert(rule) {
+        to_visit.extend(self.callees(rule).into_iter());
+      }
+    }
+    reachable
+  }
+}
+
+struct RuleCalls<'a: 'c, 'b: 'a, 'c, ExprInfo: 'c>
+{
+  grammar: &'c Grammar<'a, 'b, ExprInfo>,
+  callees: Vec<Ident>
+}
+
+impl<'a, 'b, 'c, ExprInfo> RuleCalls<'a, 'b, 'c, ExprInfo>
+{
+  fn new(grammar: &'c Grammar<'a, 'b, ExprInfo>) -> Self {
+    RuleCalls {
+      grammar: grammar,
+      callees: vec![]
+    }
+  }
+}
+
+impl<'a, 'b, 'c, ExprInfo> ExprByIndex for RuleCalls<'a, 'b, 'c, ExprInfo>
+{
+  fn expr_by_index(&self, index: usize) -> Expression {
+    self.grammar.expr_by_index(index)
+  }
+}
+
+impl<'a, 'b, 'c, ExprInfo> Visitor<()> for RuleCalls<'a, 'b, 'c, ExprInfo>
+{
+  unit_visitor_impl!(str_literal);
+  unit_visitor_impl!(atom);
+  unit_visitor_impl!(sequence);
+  unit_visitor_impl!(choice);
+
+  fn visit_non_terminal_symbol(&mut self, _this: usize, rule: Ident) {
+    if !self.callees.contains(&rule) {
+      self.callees.push(rule);
+    }
+  }
+}
@@ -18,6 +18,8 @@ pub use syntax::ast::*;
 pub use syntax::print::pprust::*;
 pub use syntax::print::pp;
 pub use syntax::util::small_vector::SmallVector;
+pub use syntax::util::ThinVec;
+pub use syntax::util::lev_distance::lev_distance;
 pub use syntax::codemap::{DUMMY_SP, NO_EXPANSION, Span, MultiSpan, Spanned, respan, BytePos};
 pub use syntax::errors::*;
 pub use syntax::ext::base::{ExtCtxt,MacResult,MacEager,DummyResult};
@@ -30,6 +32,7 @@ pub use syntax::parse::parser::Parser;
 pub use syntax::parse::ParseSess;
 pub use syntax::parse::PResult;
 pub use syntax::parse::new_parser_from_tts;
+pub use syntax::parse::new_parser_from_source_str;
 pub use syntax::parse::token::Token;
 pub use syntax::parse::lexer;
 pub use syntax::parse::lexer::TokenAndSpan;
@@ -41,3 +44,5 @@ pub use syntax::parse::parser::PathStyle;
 
 pub use syntax::symbol::Symbol;
 pub use syntax::symbol::keywords::Keyword;
+pub use syntax::symbol::keywords;
+pub use syntax::attr;
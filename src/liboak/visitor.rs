@@ -35,6 +35,11 @@ pub trait Visitor<R> : ExprByIndex
     self.visit_atom(this)
   }
 
+  /// A cut behaves like the empty literal `""` for most analyses.
+  fn visit_cut(&mut self, this: usize) -> R {
+    self.visit_str_literal(this, String::new())
+  }
+
   fn visit_spanned_expr(&mut self, _this: usize, child: usize) -> R {
     self.visit_expr(child)
   }
@@ -70,7 +75,11 @@ pub trait Visitor<R> : ExprByIndex
     self.visit_syntactic_predicate(this, child)
   }
 
-  fn visit_semantic_action(&mut self, _this: usize, child: usize, _action: Ident) -> R {
+  fn visit_semantic_action(&mut self, _this: usize, child: usize, _action: RustAction) -> R {
+    self.visit_expr(child)
+  }
+
+  fn visit_semantic_predicate(&mut self, _this: usize, child: usize, _pred: RExpr) -> R {
     self.visit_expr(child)
   }
 
@@ -138,12 +147,18 @@ pub fn walk_expr<R, V: ?Sized>(visitor: &mut V, this: usize) -> R where
     SemanticAction(child, action) => {
       visitor.visit_semantic_action(this, child, action)
     }
+    SemanticPredicate(child, pred) => {
+      visitor.visit_semantic_predicate(this, child, pred)
+    }
     TypeAscription(child, ty) => {
       visitor.visit_type_ascription(this, child, ty)
     }
     SpannedExpr(child) => {
       visitor.visit_spanned_expr(this, child)
     }
+    Cut => {
+      visitor.visit_cut(this)
+    }
   }
 }
 
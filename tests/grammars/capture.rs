@@ -0,0 +1,34 @@
+// Copyright 2017 Pierre Talbot (IRCAM)
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+grammar! capture {
+  #![skip = "spacing"]
+
+  pub identifiers = identifier*
+
+  #[no_skip]
+  identifier = $(["a-zA-Z_"] ["a-zA-Z0-9_"]*)
+
+  #[no_skip]
+  spacing = [" \n\t"]* -> (^)
+}
+
+#[test]
+fn test_capture() {
+  use oak_runtime::*;
+  use self::capture::*;
+
+  assert_eq!(parse_identifiers("x1 _y z".into_state()).unwrap_data(),
+    vec!["x1", "_y", "z"]);
+}
@@ -0,0 +1,37 @@
+// Copyright 2017 Pierre Talbot (IRCAM)
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+grammar! cut {
+  statement
+    = "if" ^ " " name -> ()
+    / name -> ()
+
+  name = ["a-z"]+ -> ()
+}
+
+#[test]
+fn test_cut() {
+  use oak_runtime::*;
+  use self::cut::*;
+
+  assert!(recognize_statement("if x".into_state()).is_successful());
+  assert!(recognize_statement("iffy".into_state()).is_failed());
+  // Without the cut, `ifx` would be recognized by the second branch.
+  match recognize_statement("ifx".into_state()).into_result() {
+    ParseResult::Failure(expectation) => {
+      assert_eq!(expectation.expected_items(), format!("` `"));
+    }
+    _ => panic!("`ifx` should not be recognized.")
+  }
+}
@@ -0,0 +1,36 @@
+// Copyright 2017 Pierre Talbot (IRCAM)
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+grammar! declared_type {
+  pair: (u32, Vec<u32>) = number ("," number)*
+
+  number: u32 = ["0-9"]+ > to_number
+
+  digits: Vec<char> = ["0-9"]+
+
+  spacing: () = [" "]* -> (^)
+
+  fn to_number(digits: Vec<char>) -> u32 {
+    digits.into_iter().fold(0, |acc, d| acc * 10 + d.to_digit(10).unwrap())
+  }
+}
+
+#[test]
+fn test_declared_type() {
+  use oak_runtime::*;
+  use self::declared_type::*;
+
+  assert_eq!(parse_pair("1,22".into_state()).unwrap_data(), (1, vec![22]));
+  assert_eq!(parse_digits("12".into_state()).unwrap_data(), vec!['1', '2']);
+}
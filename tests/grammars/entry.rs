@@ -0,0 +1,31 @@
+// Copyright 2017 Pierre Talbot (IRCAM)
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+grammar! entry {
+  pub sum = number ("+" number)* -> ()
+
+  #[entry]
+  product = number ("*" number)* -> ()
+
+  number = ["0-9"]+ -> ()
+}
+
+#[test]
+fn test_entry() {
+  use oak_runtime::*;
+  use self::entry::*;
+
+  assert!(recognize_sum("1+2".into_state()).is_successful());
+  assert!(parse_product("1*2".into_state()).is_successful());
+}
@@ -0,0 +1,143 @@
+// Copyright 2017 Pierre Talbot (IRCAM)
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+grammar! export {
+  #![export_ebnf = "target/export.ebnf"]
+  #![export_w3c_ebnf = "target/export.w3c.ebnf"]
+  #![export_railroad = "target/export.html"]
+
+  pub list = "[" (number ("," number)*)? "]"
+
+  number
+    = !"0" ["0-9"]+
+    / "0"
+
+  pub consonant = !["aeiou"] ["a-z"]
+}
+
+fn read_export(path: &str) -> String {
+  use std::fs::File;
+  use std::io::Read;
+
+  let mut content = String::new();
+  File::open(path).unwrap().read_to_string(&mut content).unwrap();
+  content
+}
+
+#[test]
+fn test_export_ebnf() {
+  assert_eq!(read_export("target/export.ebnf"),
+    "(* Grammar `export` exported by Oak. Choices are ordered: the first alternative that matches is chosen. \
+     Syntactic predicates, semantic predicates and cuts are written as comments; semantic actions and types are dropped. *)\n\n\
+     list = \"[\" , [ number , { \",\" , number } ] , \"]\" ;\n\
+     number = (* !\"0\" *) ? [\"0-9\"] ? , { ? [\"0-9\"] ? } | \"0\" ;\n\
+     consonant = (* !? [\"aeiou\"] ? *) ? [\"a-z\"] ? ;\n");
+  assert_eq!(read_export("target/export.w3c.ebnf"),
+    "/* Grammar `export` exported by Oak. Choices are ordered: the first alternative that matches is chosen. \
+     Syntactic predicates, semantic predicates and cuts are written as comments, except `!B A` matching a single character \
+     which is written `A - B`; semantic actions and types are dropped. */\n\n\
+     list ::= \"[\" ( number ( \",\" number )* )? \"]\"\n\
+     number ::= /* !\"0\" */ [0-9]+ | \"0\"\n\
+     consonant ::= [a-z] - [aeiou]\n");
+}
+
+#[test]
+fn test_export_railroad() {
+  let html = read_export("target/export.html");
+  assert!(html.starts_with("<!DOCTYPE html>"));
+  assert!(html.contains("<h2 id=\"list\">list</h2>"));
+  assert!(html.contains("<h2 id=\"number\">number</h2>"));
+  assert!(html.contains("<a xlink:href=\"#number\">"));
+  assert_eq!(html.matches("<svg").count(), 3);
+}
+
+grammar! export_dot {
+  #![export_dot = "target/export.dot"]
+
+  pub expression = nested number
+
+  nested = "(" nested? ")"
+
+  number = ["0-9"]+
+
+  unused = "x"
+}
+
+#[test]
+fn test_export_dot() {
+  let dot = read_export("target/export.dot");
+  assert!(dot.starts_with("digraph export_dot {\n"));
+  assert!(dot.contains("label=\"Infinite recursive types reduced to (^):\\lnested -> nested\\l\";\n"));
+  assert!(dot.contains("  nested [label=\"nested\\n()\", style=filled, fillcolor=\"#f4cccc\"];\n"));
+  assert!(dot.contains("  number [label=\"number\\nVec<char>\"];\n"));
+  assert!(dot.contains("  unused [label=\"unused\\n()\", color=grey, fontcolor=grey];\n"));
+  assert!(dot.contains("  expression -> nested;\n  expression -> number;\n"));
+  assert!(dot.contains("  nested -> nested [color=red];\n"));
+}
+
+grammar! export_editor {
+  #![export_tree_sitter = "target/export.tree-sitter.json"]
+  #![export_textmate = "target/export.tmLanguage.json"]
+  #![skip = "spacing"]
+
+  pub statement = keyword identifier ";"
+
+  #[no_skip]
+  #[scope = "keyword.control"]
+  keyword = "let" !["a-z"]
+
+  #[no_skip]
+  #[scope = "variable.other"]
+  identifier = ["a-z"]+
+
+  spacing = [" "]*
+}
+
+#[test]
+fn test_export_tree_sitter() {
+  let json = read_export("target/export.tree-sitter.json");
+  assert!(json.starts_with("{\n  \"name\": \"export_editor\",\n  \"rules\": {\n    \"statement\": {\n      \"type\": \"SEQ\",\n"));
+  assert!(json.contains("\"keyword\": {\n      \"type\": \"TOKEN\",\n"));
+  assert!(json.contains("\"type\": \"REPEAT1\""));
+  assert!(!json.contains("spacing"));
+  assert!(json.ends_with("\"extras\": [\n    {\n      \"type\": \"PATTERN\",\n      \"value\": \"[ ]\"\n    }\n  ]\n}\n"));
+}
+
+#[test]
+fn test_export_textmate() {
+  assert_eq!(read_export("target/export.tmLanguage.json"),
+r##"{
+  "name": "export_editor",
+  "scopeName": "source.export_editor",
+  "patterns": [
+    {
+      "include": "#keyword"
+    },
+    {
+      "include": "#identifier"
+    }
+  ],
+  "repository": {
+    "keyword": {
+      "name": "keyword.control",
+      "match": "let(?![a-z])"
+    },
+    "identifier": {
+      "name": "variable.other",
+      "match": "[a-z]+"
+    }
+  }
+}
+"##);
+}
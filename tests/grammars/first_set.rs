@@ -0,0 +1,53 @@
+// Copyright 2017 Pierre Talbot (IRCAM)
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! The branches of a choice which cannot start with the next character are skipped, the expectations reported on failure must be the same as if they were tried.
+
+grammar! first_set {
+  pub keyword
+    = "fn" / "for" / "if" / "impl" / "in"
+    / ["0-9"]+
+    / operator
+    / "l" ("et" / "oop")
+
+  #[label = "operator"]
+  operator = "+" / "-" / "*" / "/"
+}
+
+#[test]
+fn test_first_set_dispatch() {
+  use oak_runtime::*;
+  use self::first_set::*;
+  use grammars::accepted;
+
+  for input in &["fn", "for", "if", "impl", "in", "42", "-", "let", "loop"] {
+    assert!(accepted(recognize_keyword(input.into_state())), "`{}` must be accepted", input);
+  }
+  assert!(!accepted(recognize_keyword("impls".into_state())));
+  assert!(!accepted(recognize_keyword("lo".into_state())));
+}
+
+#[test]
+fn test_first_set_expectations() {
+  use oak_runtime::*;
+  use self::first_set::*;
+
+  let expected = |input: &str| {
+    let state = recognize_keyword(input.into_state());
+    state.expected.iter().map(|e| e.to_string()).collect::<Vec<_>>()
+  };
+  assert_eq!(expected("z"),
+    vec!["fn", "for", "if", "impl", "in", "[\"0-9\"]", "operator", "l"]);
+  assert_eq!(expected("lo"), vec!["et", "oop"]);
+}
@@ -0,0 +1,97 @@
+// Copyright 2017 Pierre Talbot (IRCAM)
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+grammar! generated_ast {
+  #![generate_ast]
+
+  pub expression = sum
+
+  sum = product ("+" product)*
+
+  product = factor ("*" factor)*
+
+  factor
+    = number
+    / "(" expression ")"
+
+  number = ["0-9"]+ > to_number
+
+  fn to_number(digits: Vec<char>) -> u32 {
+    digits.into_iter().fold(0, |acc, d| acc * 10 + d.to_digit(10).unwrap())
+  }
+}
+
+#[test]
+fn test_generated_ast() {
+  use oak_runtime::*;
+  use self::generated_ast::*;
+
+  fn number(n: u32) -> Product {
+    Product { factor: Box::new(Factor::Number(n)), factor_2: vec![] }
+  }
+
+  let one_plus_two = Expression {
+    sum: Box::new(Sum { product: Box::new(number(1)), product_2: vec![number(2)] })
+  };
+  assert_eq!(parse_expression("1+2".into_state()).unwrap_data(), one_plus_two.clone());
+
+  let nested = Expression {
+    sum: Box::new(Sum {
+      product: Box::new(Product {
+        factor: Box::new(Factor::Branch1(Box::new(one_plus_two))),
+        factor_2: vec![Factor::Number(3)]
+      }),
+      product_2: vec![]
+    })
+  };
+  assert_eq!(parse_expression("(1+2)*3".into_state()).unwrap_data(), nested);
+}
+
+#[test]
+fn test_generated_visitor_fold() {
+  use oak_runtime::*;
+  use self::generated_ast::*;
+
+  struct Numbers(Vec<u32>);
+
+  impl Visitor for Numbers {
+    fn visit_factor(&mut self, node: &Factor) {
+      if let &Factor::Number(n) = node {
+        self.0.push(n);
+      }
+      walk_factor(self, node)
+    }
+  }
+
+  struct Double;
+
+  impl Fold for Double {
+    fn fold_factor(&mut self, node: Factor) -> Factor {
+      match fold_factor(self, node) {
+        Factor::Number(n) => Factor::Number(n * 2),
+        node => node
+      }
+    }
+  }
+
+  let ast = parse_expression("(1+2)*3+4".into_state()).unwrap_data();
+  let mut numbers = Numbers(vec![]);
+  numbers.visit_expression(&ast);
+  assert_eq!(numbers.0, vec![1, 2, 3, 4]);
+
+  let doubled = Double.fold_expression(ast);
+  let mut numbers = Numbers(vec![]);
+  numbers.visit_expression(&doubled);
+  assert_eq!(numbers.0, vec![2, 4, 6, 8]);
+}
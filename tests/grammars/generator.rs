@@ -0,0 +1,78 @@
+// Copyright 2017 Pierre Talbot (IRCAM)
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+grammar! generator {
+  #![generator]
+
+  pub program = statement+
+
+  statement
+    = let_binding
+    / expression ";"
+
+  let_binding = "let " identifier "=" expression ";"
+
+  expression = term ("+" term)*
+
+  term
+    = number
+    / identifier
+    / "(" expression ")"
+
+  identifier = !"let" ["a-z"]+
+
+  number = ["0-9"]+
+}
+
+#[test]
+fn test_generator() {
+  use oak_runtime::*;
+  use oak_runtime::generator::Generator;
+  use self::generator::*;
+  use grammars::accepted;
+
+  let mut gen = Generator::new(2017);
+  for _ in 0..50 {
+    let input = generate_program(&mut gen).expect("a valid program");
+    assert!(accepted(recognize_program(input.as_str().into_state())));
+    let invalid = generate_invalid_program(&mut gen).expect("an invalid program");
+    assert!(!accepted(recognize_program(invalid.as_str().into_state())));
+  }
+  let identifier = generate_identifier(&mut gen).unwrap();
+  assert!(!identifier.starts_with("let"));
+}
+
+#[test]
+fn test_generator_reproducibility() {
+  use oak_runtime::generator::Generator;
+  use self::generator::*;
+
+  let mut gen1 = Generator::new(42);
+  let mut gen2 = Generator::new(42);
+  for _ in 0..10 {
+    assert_eq!(generate_program(&mut gen1), generate_program(&mut gen2));
+  }
+}
+
+#[test]
+fn test_generator_depth() {
+  use oak_runtime::generator::Generator;
+  use self::generator::*;
+
+  let mut gen = Generator::new(1);
+  gen.max_depth = 0;
+  for _ in 0..10 {
+    assert!(generate_expression(&mut gen).is_some());
+  }
+}
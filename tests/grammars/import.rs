@@ -0,0 +1,66 @@
+// Copyright 2017 Pierre Talbot (IRCAM)
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+grammar! import_pest {
+  #![import_pest = "data/import/calc.pest"]
+}
+
+grammar! import_abnf {
+  #![import_abnf = "data/import/date.abnf"]
+}
+
+grammar! import_w3c_ebnf {
+  #![import_w3c_ebnf = "data/import/list.ebnf"]
+}
+
+#[test]
+fn test_import_pest() {
+  use oak_runtime::*;
+  use self::import_pest::*;
+  use grammars::accepted;
+
+  assert!(accepted(recognize_calc("1 + 2*(3 - 4)".into_state())));
+  assert!(accepted(recognize_calc("1_000 /* thousand */ - 12".into_state())));
+  assert!(!accepted(recognize_calc("1234".into_state())));
+  assert!(!accepted(recognize_calc("1 000".into_state())));
+}
+
+#[test]
+fn test_import_abnf() {
+  use oak_runtime::*;
+  use self::import_abnf::*;
+  use grammars::accepted;
+
+  assert!(accepted(recognize_full_date("2017-08-30".into_state())));
+  assert!(!accepted(recognize_full_date("2017-13-30".into_state())));
+  assert!(accepted(recognize_boolean("TRUE".into_state())));
+  assert!(accepted(recognize_boolean("False".into_state())));
+  assert!(accepted(recognize_boolean("T".into_state())));
+  assert!(!accepted(recognize_boolean("t".into_state())));
+}
+
+#[test]
+fn test_import_w3c_ebnf() {
+  use oak_runtime::*;
+  use self::import_w3c_ebnf::*;
+  use grammars::accepted;
+
+  assert!(accepted(recognize_list("[1,x,abc]".into_state())));
+  assert!(accepted(recognize_list("[]".into_state())));
+  assert!(!accepted(recognize_list("[in]".into_state())));
+  assert!(!accepted(recognize_list("[1,int]".into_state())));
+  // Identifiers starting with a keyword are not keywords.
+  assert!(accepted(recognize_list("[index,integer,int_]".into_state())));
+  assert!(!accepted(recognize_list("[1,]".into_state())));
+}
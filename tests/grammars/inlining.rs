@@ -0,0 +1,94 @@
+// Copyright 2017 Pierre Talbot (IRCAM)
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Small rules are inlined at their call sites and the nested sequences and choices are flattened, the values and the expectations must be the same as with the calls.
+
+grammar! inlining {
+  #![skip = "spacing"]
+  #![inline_threshold = 6]
+
+  pub pair = number comma number
+
+  pub list = lbracket (value (comma value)*)? rbracket -> (^)
+
+  value = int / list / (keyword / ident)
+
+  int = digits -> (^)
+
+  #[no_skip]
+  pub keyword = "tr" "ue" / "fa" "lse"
+
+  #[no_skip]
+  ident = ["a-z"]+ -> (^)
+
+  number = digits > to_number
+
+  #[no_skip]
+  digits = ["0-9"]+
+
+  comma = ","
+  lbracket = "["
+  rbracket = "]"
+
+  #[no_skip]
+  spacing = [" "]* -> (^)
+
+  fn to_number(digits: Vec<char>) -> u32 {
+    digits.into_iter().fold(0, |accu, d| accu * 10 + d.to_digit(10).unwrap())
+  }
+}
+
+grammar! no_inlining {
+  #![inline_threshold = 0]
+
+  pub pair = number "," number
+
+  pub keyword = "tr" "ue"
+
+  number = ["0-9"]+ > to_number
+
+  fn to_number(digits: Vec<char>) -> u32 {
+    digits.into_iter().fold(0, |accu, d| accu * 10 + d.to_digit(10).unwrap())
+  }
+}
+
+#[test]
+fn test_inlining() {
+  use oak_runtime::*;
+  use self::inlining::*;
+  use grammars::accepted;
+
+  assert_eq!(parse_pair("12 , 7".into_state()).unwrap_data(), (12, 7));
+  assert_eq!(self::no_inlining::parse_pair("12,7".into_state()).unwrap_data(), (12, 7));
+
+  for input in &["[]", "[1]", "[ 1, [true, [] ], x ]", "[false,abc]"] {
+    assert!(accepted(recognize_list(input.into_state())), "`{}` must be accepted", input);
+  }
+  assert!(!accepted(recognize_list("[1,]".into_state())));
+  assert!(!accepted(recognize_list("[1 2]".into_state())));
+}
+
+#[test]
+fn test_inlining_expectations() {
+  use oak_runtime::*;
+  use self::inlining::*;
+
+  let expected = |state: ParseState<StrStream, ()>|
+    state.expected.iter().map(|e| e.to_string()).collect::<Vec<_>>();
+  assert_eq!(expected(recognize_list("[1 2]".into_state())), vec!["[\" \"]", ",", "]"]);
+  // Adjacent literals are merged, `"tr" "ue"` fails with the expectation `true`.
+  assert_eq!(expected(recognize_keyword("tx".into_state())), vec!["true", "false"]);
+  // Without inlining, the literals are not merged and `"tr" "ue"` fails after `"tr"`.
+  assert_eq!(expected(self::no_inlining::recognize_keyword("trx".into_state())), vec!["ue"]);
+}
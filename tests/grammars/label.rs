@@ -0,0 +1,42 @@
+// Copyright 2017 Pierre Talbot (IRCAM)
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+grammar! label {
+  sum = number ("+" number)* -> ()
+
+  #[label = "number"]
+  number
+    = ["0-9"]+ -> ()
+    / "(" sum ")"
+}
+
+#[test]
+fn test_label() {
+  use oak_runtime::*;
+  use self::label::*;
+
+  match recognize_sum("1+a".into_state()).into_result() {
+    ParseResult::Partial(_, expectation) => {
+      assert_eq!(expectation.expected_items(), format!("`number`"));
+    }
+    _ => panic!("`1+a` should be partially recognized.")
+  }
+  // The rule `number` consumed `(1` so its expectations are kept.
+  match recognize_sum("(1".into_state()).into_result() {
+    ParseResult::Failure(expectation) => {
+      assert!(!expectation.expected_items().contains("number"));
+    }
+    _ => panic!("`(1` should not be recognized.")
+  }
+}
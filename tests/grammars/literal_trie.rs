@@ -0,0 +1,59 @@
+// Copyright 2017 Pierre Talbot (IRCAM)
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Choices of literals are compiled into a trie, the first literal of the choice which is a prefix of the input must be chosen and the expectations of the literals before it must be recorded.
+
+grammar! literal_trie {
+  #![skip = "spacing"]
+
+  pub keyword = "int" / "in" / "impl" / "if" / "i" / "fn" / "for"
+
+  pub keywords = keyword+
+
+  #[no_skip]
+  pub operator = "<<=" / "<=" / "<<" / "<" / "="
+
+  spacing = [" "]*
+}
+
+#[test]
+fn test_literal_trie() {
+  use oak_runtime::*;
+  use self::literal_trie::*;
+  use grammars::accepted;
+
+  for input in &["int", "in", "impl", "if", "i", "fn", "for", "for  in int i"] {
+    assert!(accepted(recognize_keywords(input.into_state())), "`{}` must be accepted", input);
+  }
+  assert!(!accepted(recognize_keywords("im".into_state())));
+  assert!(!accepted(recognize_keywords("f".into_state())));
+
+  for input in &["<<=", "<=", "<<", "<", "="] {
+    assert!(accepted(recognize_operator(input.into_state())), "`{}` must be accepted", input);
+  }
+  assert!(!accepted(recognize_operator("< ".into_state())));
+}
+
+#[test]
+fn test_literal_trie_expectations() {
+  use oak_runtime::*;
+  use self::literal_trie::*;
+
+  let expected = |input: &str| {
+    let state = recognize_operator(input.into_state());
+    state.expected.iter().map(|e| e.to_string()).collect::<Vec<_>>()
+  };
+  assert_eq!(expected("="), vec!["<<=", "<=", "<<", "<"]);
+  assert_eq!(expected("x"), vec!["<<=", "<=", "<<", "<", "="]);
+}
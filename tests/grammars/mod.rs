@@ -14,6 +14,8 @@
 
 #![allow(dead_code)]
 
+use oak_runtime::{ParseState, ParseResult, HasNext};
+
 pub mod ntcc;
 pub mod type_name;
 pub mod calc;
@@ -21,4 +23,36 @@ pub mod recursive_type;
 pub mod combinators;
 pub mod well_formedness;
 pub mod issue94;
+pub mod skip;
+pub mod rust_action;
+pub mod semantic_predicate;
+pub mod label;
+pub mod cut;
+pub mod entry;
+pub mod rule_attributes;
+pub mod declared_type;
+pub mod type_ascription;
+pub mod capture;
+pub mod generated_ast;
+pub mod printer;
+pub mod generator;
+pub mod export;
+pub mod import;
+pub mod shadowed_branch;
+pub mod unused;
+pub mod first_set;
+pub mod literal_trie;
+pub mod scanning;
+pub mod inlining;
+pub mod negated_class;
 mod stream_span;
+
+/// `true` if `state` is successful and the full input was consumed.
+pub fn accepted<S, T>(state: ParseState<S, T>) -> bool where
+ S: Ord + Clone + HasNext
+{
+  match state.into_result() {
+    ParseResult::Success(_) => true,
+    _ => false
+  }
+}
@@ -0,0 +1,51 @@
+// Copyright 2017 Pierre Talbot (IRCAM)
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! A negative predicate on single characters followed by `.` or a class is rewritten into a class of the remaining characters.
+
+grammar! negated_class {
+  pub string = "\"" $((!"\"" .)*) "\""
+
+  pub not_digits = (!["0-9"] .)+ -> (^)
+
+  pub letters = (!("x" / ["y"]) ["a-z"])+ -> (^)
+
+  pub chars = (!"a" .)*
+}
+
+#[test]
+fn test_negated_class() {
+  use oak_runtime::*;
+  use self::negated_class::*;
+  use grammars::accepted;
+
+  assert_eq!(parse_string("\"a b é\"".into_state()).unwrap_data(), "a b é");
+  assert_eq!(parse_string("\"\"".into_state()).unwrap_data(), "");
+  assert!(accepted(recognize_not_digits("ab é".into_state())));
+  assert!(!accepted(recognize_not_digits("a1".into_state())));
+  assert!(accepted(recognize_letters("abwz".into_state())));
+  assert!(!accepted(recognize_letters("abx".into_state())));
+  assert!(!accepted(recognize_letters("aby".into_state())));
+  assert_eq!(parse_chars("bcda".into_state()).unwrap_data(), vec!['b', 'c', 'd']);
+}
+
+#[test]
+fn test_negated_class_expectations() {
+  use oak_runtime::*;
+  use self::negated_class::*;
+
+  let expected = |state: ParseState<StrStream, ()>|
+    state.expected.iter().map(|e| e.to_string()).collect::<Vec<_>>();
+  assert_eq!(expected(recognize_letters("x".into_state())), vec!["[\"a-wz\"]"]);
+}
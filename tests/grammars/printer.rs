@@ -0,0 +1,95 @@
+// Copyright 2017 Pierre Talbot (IRCAM)
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+grammar! printer {
+  #![generate_ast]
+  #![generate_printer]
+  #![skip = "spacing"]
+
+  pub expression = sum
+
+  sum = product ("+" product)*
+
+  product = factor ("*" factor)*
+
+  factor
+    = number
+    / "(" expression ")"
+
+  #[no_skip]
+  number = ["0-9"]+ -> String
+
+  spacing = [" "]*
+}
+
+grammar! printer_separator {
+  #![generate_printer]
+
+  pub list = "[" number (separator number)* "]"
+
+  number = ["0-9"]+ -> String
+
+  #[print_separator = ", "]
+  separator = "," [" "]*
+}
+
+grammar! printer_escape {
+  #![generate_ast]
+  #![generate_printer]
+
+  pub quoted = "\"" character* "\""
+
+  character
+    = "\\" ["\"\\"]
+    / !["\"\\"] .
+}
+
+#[test]
+fn test_printer_round_trip() {
+  use oak_runtime::*;
+  use self::printer::*;
+
+  for input in vec!["1+2", "(1+2)*3", "4*(5+6*7)+8"] {
+    let ast = parse_expression(input.into_state()).unwrap_data();
+    let printed = print_expression(&ast);
+    assert_eq!(printed, input);
+    assert_eq!(parse_expression(printed.as_str().into_state()).unwrap_data(), ast);
+  }
+  let ast = parse_expression("( 1 + 2 ) *  3".into_state()).unwrap_data();
+  assert_eq!(print_expression(&ast), "(1+2)*3");
+}
+
+#[test]
+fn test_printer_separator() {
+  use oak_runtime::*;
+  use self::printer_separator::*;
+
+  let list = parse_list("[1,2,  3]".into_state()).unwrap_data();
+  assert_eq!(list, (String::from("1"), vec![String::from("2"), String::from("3")]));
+  assert_eq!(print_list(&list), "[1, 2, 3]");
+  assert_eq!(print_number(&String::from("42")), "42");
+}
+
+#[test]
+fn test_printer_escape() {
+  use oak_runtime::*;
+  use self::printer_escape::*;
+
+  for input in vec![r#""""#, r#""abc""#, r#""a\"b\\c""#] {
+    let ast = parse_quoted(input.into_state()).unwrap_data();
+    let printed = print_quoted(&ast);
+    assert_eq!(printed, input);
+    assert_eq!(parse_quoted(printed.as_str().into_state()).unwrap_data(), ast);
+  }
+}
@@ -0,0 +1,39 @@
+// Copyright 2017 Pierre Talbot (IRCAM)
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+grammar! rust_action {
+  sum = number ("+" number)* > |x: u32, rest: Vec<u32>| -> u32 { rest.into_iter().fold(x, |x, y| x + y) }
+
+  number = ["0-9"]+ > |raw: Vec<char>| -> u32 { raw.into_iter().collect::<String>().parse().unwrap() }
+
+  expr
+    = "-" number > Expr::Neg
+    / number > Expr::Number
+
+  #[derive(Debug, PartialEq)]
+  pub enum Expr {
+    Number(u32),
+    Neg(u32)
+  }
+}
+
+#[test]
+fn test_rust_action() {
+  use oak_runtime::*;
+  use self::rust_action::*;
+
+  assert_eq!(parse_sum("1+2+30".into_state()).unwrap_data(), 33);
+  assert_eq!(parse_expr("-7".into_state()).unwrap_data(), Expr::Neg(7));
+  assert_eq!(parse_expr("7".into_state()).unwrap_data(), Expr::Number(7));
+}
@@ -0,0 +1,59 @@
+// Copyright 2017 Pierre Talbot (IRCAM)
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Character classes with several ASCII intervals are compiled into bitmaps and the repetitions of a class or of a literal are compiled into scanning loops, they must behave and report errors as the generic combinators.
+
+grammar! scanning {
+  pub identifier = ["a-zA-Z0-9_"]+ -> (^)
+
+  pub word = ["a-zA-Zé-ë_"]* "." -> (^)
+
+  pub dashes = "--"+ -> (^)
+
+  pub optional_dashes = "--"* "|" -> (^)
+
+  pub ident_text = $(["a-zA-Z0-9_"]+)
+}
+
+#[test]
+fn test_scanning() {
+  use oak_runtime::*;
+  use self::scanning::*;
+  use grammars::accepted;
+
+  assert!(accepted(recognize_identifier("x_1Az9".into_state())));
+  assert!(!accepted(recognize_identifier("".into_state())));
+  assert!(!accepted(recognize_identifier("-a".into_state())));
+  assert!(accepted(recognize_word("Héllo.".into_state())));
+  assert!(accepted(recognize_word(".".into_state())));
+  assert!(!accepted(recognize_word("hèllo.".into_state())));
+  assert!(accepted(recognize_dashes("------".into_state())));
+  assert!(!accepted(recognize_dashes("-".into_state())));
+  assert!(accepted(recognize_optional_dashes("----|".into_state())));
+  assert!(accepted(recognize_optional_dashes("|".into_state())));
+  assert!(!accepted(recognize_optional_dashes("---|".into_state())));
+  assert_eq!(parse_ident_text("a_B2".into_state()).unwrap_data(), "a_B2");
+}
+
+#[test]
+fn test_scanning_expectations() {
+  use oak_runtime::*;
+  use self::scanning::*;
+
+  let expected = |state: ParseState<StrStream, ()>|
+    state.expected.iter().map(|e| e.to_string()).collect::<Vec<_>>();
+  assert_eq!(expected(recognize_identifier("ab-".into_state())), vec!["[\"a-zA-Z0-9_\"]"]);
+  assert_eq!(expected(recognize_word("abc".into_state())), vec!["[\"a-zA-Zé-ë_\"]", "."]);
+  assert_eq!(expected(recognize_optional_dashes("---".into_state())), vec!["--", "|"]);
+}
@@ -0,0 +1,102 @@
+// Copyright 2017 Pierre Talbot (IRCAM)
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+grammar! semantic_predicate {
+  byte = number &{ |n: &u32| *n < 256 } > |n: u32| -> u8 { n as u8 }
+
+  number = ["0-9"]+ > to_number
+
+  element
+    = "<" name ">" (!"<" .)* "</" name ">"
+      &{ |open: &String, _body: &Vec<char>, close: &String| open == close }
+      > |name: String, body: Vec<char>, _close: String| -> (String, String) {
+          (name, body.into_iter().collect())
+        }
+
+  name = ["a-z"]+ > |raw: Vec<char>| -> String { raw.into_iter().collect() }
+
+  small = ["0-9"]+ > to_small
+
+  // The alias is not named `Result` so the action is not fallible.
+  digits = ["0-9"]+ > count_digits
+
+  use std::io;
+
+  type Checked<T> = Result<T, String>;
+
+  fn to_number(raw: Vec<char>) -> Result<u32, String> {
+    let raw: String = raw.into_iter().collect();
+    raw.parse().map_err(|_| format!("number `{}` does not fit in 32 bits", raw))
+  }
+
+  fn to_small(raw: Vec<char>) -> io::Result<u8> {
+    let raw: String = raw.into_iter().collect();
+    raw.parse().map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "number does not fit in 8 bits"))
+  }
+
+  fn count_digits(raw: Vec<char>) -> Checked<usize> {
+    if raw.len() <= 3 { Ok(raw.len()) }
+    else { Err(format!("too many digits")) }
+  }
+}
+
+#[test]
+fn test_semantic_predicate() {
+  use oak_runtime::*;
+  use self::semantic_predicate::*;
+
+  assert_eq!(parse_byte("255".into_state()).unwrap_data(), 255);
+  match parse_byte("256".into_state()).into_result() {
+    ParseResult::Failure(expectation) => {
+      assert!(expectation.expected_items().contains("<semantic predicate>"));
+    }
+    _ => panic!("`256` should not fit in a byte.")
+  }
+  assert!(recognize_byte("42".into_state()).is_successful());
+  assert!(recognize_byte("256".into_state()).is_failed());
+  assert_eq!(parse_element("<a>text</a>".into_state()).unwrap_data(),
+    (format!("a"), format!("text")));
+  assert!(recognize_element("<a>text</b>".into_state()).is_failed());
+}
+
+#[test]
+fn test_fallible_action() {
+  use oak_runtime::*;
+  use self::semantic_predicate::*;
+
+  assert_eq!(parse_number("4294967295".into_state()).unwrap_data(), 4294967295);
+  match parse_number("4294967296".into_state()).into_result() {
+    ParseResult::Failure(expectation) => {
+      assert!(expectation.expected_items().contains("does not fit in 32 bits"));
+    }
+    _ => panic!("`4294967296` should overflow.")
+  }
+  assert!(recognize_number("4294967296".into_state()).is_failed());
+}
+
+#[test]
+fn test_fallible_action_alias() {
+  use oak_runtime::*;
+  use self::semantic_predicate::*;
+
+  assert_eq!(parse_small("255".into_state()).unwrap_data(), 255);
+  match parse_small("256".into_state()).into_result() {
+    ParseResult::Failure(expectation) => {
+      assert!(expectation.expected_items().contains("does not fit in 8 bits"));
+    }
+    _ => panic!("`256` should not fit in a byte.")
+  }
+  assert_eq!(parse_digits("123".into_state()).unwrap_data(), Ok(3));
+  assert_eq!(parse_digits("1234".into_state()).unwrap_data(), Err(format!("too many digits")));
+}
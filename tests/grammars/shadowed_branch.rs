@@ -0,0 +1,57 @@
+// Copyright 2017 Pierre Talbot (IRCAM)
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! The branches of these choices are reachable and must not be reported as shadowed, the commented rules are reported with a warning.
+
+grammar! shadowed_branch {
+  // a = "a" / "ab"                      // WARNING: unreachable branch
+
+  // b = identifier / keyword            // WARNING: unreachable branch
+  // keyword = "int" / "in"
+
+  // c = ["a-z"] / "x" "y"               // WARNING: unreachable branch
+
+  // d = "x" / "y" / ("x" "z" / "y" "z") // WARNING: unreachable branch
+
+  // k = "in" / "int"                    // WARNING: unreachable branch
+
+  // e = "a"* / "b"                      // WARNING: unreachable branch
+
+  pub f = "ab" / "a"
+
+  pub g = keyword / identifier
+
+  pub h = "a" "b" / "a" "c"
+
+  pub i = !"0" ["0-9"]+ / "0"
+
+  pub j = "a" "b"* "c" / "a"
+
+  identifier = ["a-z"]+
+  keyword = "int" / "in"
+}
+
+#[test]
+fn test_shadowed_branch() {
+  use oak_runtime::*;
+  use self::shadowed_branch::*;
+  use grammars::accepted;
+
+  assert!(accepted(recognize_f("a".into_state())));
+  assert!(accepted(recognize_g("int".into_state())));
+  assert!(accepted(recognize_g("integer".into_state())));
+  assert!(accepted(recognize_h("ac".into_state())));
+  assert!(accepted(recognize_i("0".into_state())));
+  assert!(accepted(recognize_j("a".into_state())));
+}
@@ -0,0 +1,79 @@
+// Copyright 2017 Pierre Talbot (IRCAM)
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+grammar! skip {
+  #![skip = "spacing"]
+
+  program = spacing sum
+
+  sum = number ("+" number)* > add
+
+  #[no_skip]
+  number = ["0-9"]+ > to_number
+
+  spacing = [" \n\t"]* -> (^)
+
+  fn add(x: u32, rest: Vec<u32>) -> u32 {
+    rest.into_iter().fold(x, |x, y| x + y)
+  }
+
+  fn to_number(raw_text: Vec<char>) -> u32 {
+    raw_text.into_iter().collect::<String>().parse().unwrap()
+  }
+}
+
+#[test]
+fn test_skip() {
+  use oak_runtime::*;
+
+  let state = skip::parse_program(" 1 +2+  30 \n".into_state());
+  assert_eq!(state.unwrap_data(), 33);
+  // `number` is lexical so spaces are not skipped inside it.
+  match skip::parse_program("1 0 + 2".into_state()).into_result() {
+    ParseResult::Partial(data, _) => assert_eq!(data, 1),
+    _ => panic!("`1 0 + 2` should be partially parsed.")
+  }
+}
+
+grammar! private_skip {
+  #![skip = "spacing"]
+  #![generate_ast]
+  #![inline_threshold = 0]
+
+  // `spacing` is only called by the calls inserted after the terminals.
+  pub sum = number ("+" number)*
+
+  #[no_skip]
+  number = ["0-9"]+ > to_number
+
+  spacing = [" "]* -> (^)
+
+  fn to_number(raw_text: Vec<char>) -> u32 {
+    raw_text.into_iter().collect::<String>().parse().unwrap()
+  }
+}
+
+#[test]
+fn test_private_skip() {
+  use oak_runtime::*;
+  use self::private_skip::*;
+
+  match parse_sum("1 + 2 +30 ".into_state()).into_result() {
+    ParseResult::Success(sum) => {
+      assert_eq!(sum.number, 1);
+      assert_eq!(sum.number_2, vec![2, 30]);
+    }
+    _ => panic!("`1 + 2 +30 ` should be fully parsed.")
+  }
+}
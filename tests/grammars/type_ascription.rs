@@ -0,0 +1,38 @@
+// Copyright 2017 Pierre Talbot (IRCAM)
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+grammar! type_ascription {
+  identifier = ["a-zA-Z_"]+ -> String
+
+  identifier_slice = ["a-zA-Z_"]+ -> &str
+
+  keyword = ("let" / "in") -> String
+
+  commas = ","+ -> Vec<_>
+
+  sign = "-"? -> Option<_>
+}
+
+#[test]
+fn test_type_ascription() {
+  use oak_runtime::*;
+  use self::type_ascription::*;
+
+  assert_eq!(parse_identifier("let_x".into_state()).unwrap_data(), String::from("let_x"));
+  assert_eq!(parse_identifier_slice("abc def".into_state()).unwrap_data(), "abc");
+  assert_eq!(parse_keyword("in".into_state()).unwrap_data(), String::from("in"));
+  assert_eq!(parse_commas(",,,".into_state()).unwrap_data(), vec![(), (), ()]);
+  assert_eq!(parse_sign("-".into_state()).unwrap_data(), Some(()));
+  assert_eq!(parse_sign("".into_state()).unwrap_data(), None);
+}
@@ -0,0 +1,49 @@
+// Copyright 2017 Pierre Talbot (IRCAM)
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! The rule `hexadecimal` is unreachable from the entry rule and the function `from_hex`, only used by it, is reported as unused. The other functions are used directly or indirectly by the actions, `digits_value` through the body of the action `to_decimal`. The reported functions are checked by the tests of `middle::analysis::reachability`.
+
+grammar! unused {
+  pub number
+    = decimal > make_number
+    / "-" decimal > |n: u32| -> i64 { negate(n) }
+
+  decimal = ["0-9"]+ > to_decimal
+
+  hexadecimal = "0x" ["0-9a-f"]+ > from_hex // WARNING: unreachable rule
+
+  fn to_decimal(digits: Vec<char>) -> u32 {
+    digits_value(digits, 10)
+  }
+
+  fn from_hex(digits: Vec<char>) -> u32 { // WARNING: unused function
+    digits_value(digits, 16)
+  }
+
+  fn digits_value(digits: Vec<char>, radix: u32) -> u32 {
+    digits.into_iter().fold(0, |n, d| n * radix + d.to_digit(radix).unwrap())
+  }
+
+  fn make_number(n: u32) -> i64 { n as i64 }
+  fn negate(n: u32) -> i64 { -(n as i64) }
+}
+
+#[test]
+fn test_unused() {
+  use oak_runtime::*;
+  use self::unused::*;
+
+  assert_eq!(parse_number("42".into_state()).unwrap_data(), 42);
+  assert_eq!(parse_number("-7".into_state()).unwrap_data(), -7);
+}